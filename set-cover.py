@@ -62,27 +62,9 @@ def expand_tags(ingredient: list[tuple[str, str]]) -> list[str]:
 
 recipes = list(map(lambda r: list(map(expand_tags, r)), recipes))
 
-# Filter out only recipes with our ingredients
-ingredients = {
-    "minecraft:oak_planks",
-    "minecraft:cobblestone",
-    "minecraft:stone",
-    "minecraft:glass",
-    "minecraft:white_wool",
-    "minecraft:stick",
-    "minecraft:coal",
-    "minecraft:diamond",
-    "minecraft:gold_ingot",
-    "minecraft:iron_ingot",
-    "minecraft:redstone",
-    "minecraft:quartz",
-    "minecraft:oak_slab",
-    "minecraft:oak_log",
-    "minecraft:iron_nugget",
-    "minecraft:redstone_torch",
-    "minecraft:string",
-    "minecraft:leather"
-}
+# Filter out only recipes with our ingredients, which are listed in the palette
+palette = json.loads(open("./palette.json", "r").read())
+ingredients = set(map(lambda m: m["id"], palette["materials"]))
 
 # Remove all except relevant elements
 recipes = list(map(lambda r: list(map(lambda i: list(filter(lambda id: id in ingredients, i)), r)), recipes))
@@ -0,0 +1,141 @@
+use std::{collections::HashMap, fs::File, io::{Read, Seek}, path::Path};
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::parse::DataSource;
+
+/// The directory, relative to a namespace, that recipes are stored in.
+const RECIPE_DIRECTORY: &str = "recipe/";
+
+/// The directory, relative to a namespace, that item tags are stored in.
+const TAG_DIRECTORY: &str = "tags/item/";
+
+/// The recipes and tags of a client jar or data pack zip.
+/// Every relevant file is read and parsed once, when the archive is opened.
+pub struct Archive {
+    recipes: Vec<(String, Value)>,
+    tags: HashMap<String, Value>,
+}
+
+impl Archive {
+    /// Reads every recipe and item tag from the archive at the provided path.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("while trying to open archive {path:?}"))?;
+
+        Self::read(file).with_context(|| format!("while trying to read archive {path:?}"))
+    }
+
+    /// Reads every recipe and item tag from an archive.
+    pub fn read(reader: impl Read + Seek) -> Result<Self> {
+        let mut zip = ZipArchive::new(reader)?;
+
+        let mut recipes = Vec::new();
+        let mut tags = HashMap::new();
+
+        for index in 0..zip.len() {
+            let mut entry = zip.by_index(index)
+                .with_context(|| format!("while trying to read entry {index}"))?;
+
+            let name = entry.name().to_owned();
+
+            let (id, is_recipe) = if let Some(id) = entry_id(&name, RECIPE_DIRECTORY) {
+                (id, true)
+            } else if let Some(id) = entry_id(&name, TAG_DIRECTORY) {
+                (id, false)
+            } else {
+                continue;
+            };
+
+            let mut string = String::new();
+            entry.read_to_string(&mut string)
+                .with_context(|| format!("while trying to read {name}"))?;
+
+            let json = serde_json::from_str::<Value>(&string)
+                .with_context(|| format!("while trying to parse json from {name}"))?;
+
+            if is_recipe {
+                recipes.push((id, json));
+            } else {
+                tags.insert(id, json);
+            }
+        }
+
+        Ok(Self { recipes, tags })
+    }
+}
+
+impl DataSource for Archive {
+    fn recipes(&self) -> Result<Vec<(String, Value)>> {
+        Ok(self.recipes.clone())
+    }
+
+    fn tag(&self, id: &str) -> Result<Option<Value>> {
+        Ok(self.tags.get(id).cloned())
+    }
+}
+
+/// Converts an archive entry path like `data/<namespace>/<directory><name>.json`
+/// to the ID `<namespace>:<name>`, if it's inside the provided directory.
+fn entry_id(path: &str, directory: &str) -> Option<String> {
+    let (namespace, rest) = path.strip_prefix("data/")?.split_once('/')?;
+
+    let name = rest.strip_prefix(directory)?.strip_suffix(".json")?;
+
+    Some(format!("{namespace}:{name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::*;
+
+    #[test]
+    fn converts_entry_paths_to_ids() {
+        assert_eq!(entry_id("data/minecraft/recipe/piston.json", RECIPE_DIRECTORY).as_deref(), Some("minecraft:piston"));
+        assert_eq!(entry_id("data/c/tags/item/ores/iron.json", TAG_DIRECTORY).as_deref(), Some("c:ores/iron"));
+
+        assert_eq!(entry_id("data/minecraft/recipe/piston.json", TAG_DIRECTORY), None);
+        assert_eq!(entry_id("data/minecraft/recipes/piston.json", RECIPE_DIRECTORY), None);
+        assert_eq!(entry_id("assets/minecraft/recipe/piston.json", RECIPE_DIRECTORY), None);
+        assert_eq!(entry_id("data/minecraft/recipe/piston.txt", RECIPE_DIRECTORY), None);
+    }
+
+    #[test]
+    fn reads_recipes_and_tags() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+        for (name, contents) in [
+            ("data/minecraft/recipe/stick.json", r#"{"type":"minecraft:crafting_shaped"}"#),
+            ("data/c/tags/item/rods.json", r#"{"values":["minecraft:stick"]}"#),
+            ("pack.mcmeta", r#"{"pack":{}}"#),
+        ] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+
+        let archive = Archive::read(zip.finish().unwrap()).unwrap();
+
+        let recipes = archive.recipes().unwrap();
+        assert_eq!(recipes.len(), 1);
+        assert_eq!(recipes[0].0, "minecraft:stick");
+        assert_eq!(recipes[0].1["type"], "minecraft:crafting_shaped");
+
+        assert_eq!(archive.tag("c:rods").unwrap().unwrap()["values"][0], "minecraft:stick");
+        assert!(archive.tag("minecraft:rods").unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("data/minecraft/recipe/stick.json", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"{").unwrap();
+
+        assert!(Archive::read(zip.finish().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use std::{borrow::Cow, io::{self, BufRead, Write}};
+
+use anyhow::{anyhow, Context, Result};
+use itertools::Itertools;
+
+use crate::{assemble_pools, describe, matrix::PackedHint, most_information, next_guesses, notation::{parse_notation, render}, Answer, Color, Craft, Hint, SolverContext};
+
+/// The most candidate names that are listed at each step.
+const LISTED_NAMES: usize = 20;
+
+/// A guess that was made, and the answers that were possible before it.
+struct Step {
+    guess: Craft<'static>,
+    hint: Hint,
+    answers: Vec<Answer>,
+}
+
+/// Runs an interactive session that suggests guesses for a real game and reads
+/// their hints from standard input, narrowing down the answers as it goes.
+pub fn assist(answers: &[Answer], guesses: &[Craft], ctx: SolverContext) -> Result<()> {
+    let mut steps: Vec<Step> = Vec::new();
+    let mut lines = io::stdin().lock().lines();
+
+    println!("Enter the hint for each guess as 9 of `G` (green), `Y` (yellow), or `.` (gray), in reading order.");
+    println!("Enter `guess <grid>` if you guessed something else (e.g. `guess PPP/CiC/CrC`), `undo` to take back the last guess, or `quit` to stop.");
+
+    loop {
+        let remaining = steps.last()
+            .map(|step| next_answers(step, ctx))
+            .unwrap_or_else(|| answers.to_vec());
+
+        print_candidates(&remaining, ctx);
+
+        // In hard mode, only suggest guesses consistent with every hint so far
+        let allowed = steps.iter().fold(Cow::Borrowed(guesses), |allowed, step| {
+            Cow::Owned(next_guesses(&allowed, &next_answers(step, ctx), ctx, &step.guess, &step.hint).into_owned())
+        });
+
+        let mut guess = match &remaining[..] {
+            [answer] => answer.craft,
+            _ => *most_information(&remaining, &allowed, ctx).first().unwrap().1,
+        };
+
+        println!("Guess {}: {}", steps.len() + 1, describe(&guess, ctx));
+        println!("{}", render(&guess, None, ctx.palette));
+
+        let hint = loop {
+            print!("> ");
+            io::stdout().flush()?;
+
+            let Some(line) = lines.next() else {
+                return Ok(());
+            };
+
+            let line = line.context("while reading from standard input")?;
+
+            match line.split_whitespace().collect_vec()[..] {
+                [] => continue,
+                ["quit"] => return Ok(()),
+                ["undo"] => break None,
+                ["guess", ref grid @ ..] => match parse_notation(&grid.join(" "), ctx.palette) {
+                    Ok(grid) => {
+                        guess = grid;
+                        println!("Guess {}: {}", steps.len() + 1, describe(&guess, ctx));
+                        println!("{}", render(&guess, None, ctx.palette));
+                    },
+                    Err(error) => println!("{error}"),
+                },
+                [hint] => match parse_hint(hint) {
+                    Ok(hint) if !assemble_pools(&guess, &remaining, ctx.matrix).contains_key(&PackedHint::pack(&hint)) => {
+                        println!("No remaining answer gives that hint for this guess; check it and try again.");
+                    },
+                    Ok(hint) => break Some(hint),
+                    Err(error) => println!("{error}"),
+                },
+                _ => println!("Unknown input; enter a hint, `guess <grid>`, `undo`, or `quit`."),
+            }
+        };
+
+        match hint {
+            Some(hint) if hint == [Color::Green; 9] => {
+                println!("{}", render(&guess, Some(&hint), ctx.palette));
+                println!("Solved in {} guesses.", steps.len() + 1);
+                return Ok(());
+            },
+            Some(hint) => {
+                println!("{}", render(&guess, Some(&hint), ctx.palette));
+                steps.push(Step { guess, hint, answers: remaining });
+            },
+            None => match steps.pop() {
+                Some(step) => println!("Undid guess {}: {}", steps.len() + 1, describe(&step.guess, ctx)),
+                None => println!("There's nothing to undo."),
+            },
+        }
+    }
+}
+
+/// The answers that are still possible after a step.
+fn next_answers(step: &Step, ctx: SolverContext) -> Vec<Answer> {
+    assemble_pools(&step.guess, &step.answers, ctx.matrix)
+        .remove(&PackedHint::pack(&step.hint))
+        .expect("hints are checked against the pools when entered")
+}
+
+/// Prints the number of remaining answers, and the recipes they belong to.
+fn print_candidates(answers: &[Answer], ctx: SolverContext) {
+    let recipes = answers.iter().map(|answer| ctx.labels.name(answer.origin.recipe)).unique().collect_vec();
+
+    let listed = recipes.iter().take(LISTED_NAMES).join(", ");
+
+    match recipes.len().checked_sub(LISTED_NAMES) {
+        Some(more) if more > 0 => println!("{} answers remaining, from {listed}, and {more} more", answers.len()),
+        _ => println!("{} answers remaining, from {listed}", answers.len()),
+    }
+}
+
+/// Parses a hint of 9 colors: `G` for green, `Y` for yellow, and `.` for gray.
+pub fn parse_hint(text: &str) -> Result<Hint> {
+    let colors = text.chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            'G' => Ok(Color::Green),
+            'Y' => Ok(Color::Yellow),
+            '.' => Ok(Color::Gray),
+            c => Err(anyhow!("invalid color {c:?} in hint; expected `G`, `Y`, or `.`")),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    colors.try_into().map_err(|colors: Vec<_>| anyhow!("expected 9 colors in hint, found {}", colors.len()))
+}
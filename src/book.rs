@@ -0,0 +1,157 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use itertools::Itertools;
+use serde_json::{json, Map, Value};
+
+use crate::{assistant::parse_hint, notation::parse_notation, palette::Palette, parse::read_json, tree::{hint_to_string, Node}, Craft, Hint};
+
+/// The branch key that matches every hint without a branch of its own.
+const ANY_HINT: &str = "*";
+
+/// An opening book: a guess to force, and the book to follow after each hint
+/// it can result in. Once the book runs out, the solver picks guesses itself.
+#[derive(Debug, Clone)]
+pub struct Book {
+    pub guess: Craft<'static>,
+    branches: HashMap<Hint, Book>,
+    /// The book to follow after any hint without a branch.
+    otherwise: Option<Box<Book>>,
+}
+
+impl Book {
+    /// Reads a book from a JSON file.
+    pub fn load(path: &Path, palette: &Palette) -> Result<Self> {
+        let json = read_json(path)?;
+
+        Self::parse(&json, palette).with_context(|| format!("while parsing opening book from path {path:?}"))
+    }
+
+    /// Parses a book from JSON: an object with a `guess` of 9 item IDs (or a
+    /// string in grid notation), and an optional object `branches` from hints
+    /// (like `GY.......`, or `*` for any other hint) to the book to follow
+    /// after them.
+    pub fn parse(json: &Value, palette: &Palette) -> Result<Self> {
+        let guess = match json.get("guess") {
+            Some(Value::String(notation)) => parse_notation(notation, palette)?,
+            Some(Value::Array(ids)) => parse_ids(ids, palette)?,
+            _ => return Err(anyhow!("expected array or string at path 'guess'")),
+        };
+
+        let mut book = Book { guess, branches: HashMap::new(), otherwise: None };
+
+        let Some(branches) = json.get("branches") else {
+            return Ok(book);
+        };
+
+        let Some(branches) = branches.as_object() else {
+            return Err(anyhow!("expected object at path 'branches'"));
+        };
+
+        for (key, next) in branches {
+            let next = Self::parse(next, palette).with_context(|| format!("while parsing branch {key:?}"))?;
+
+            if key == ANY_HINT {
+                book.otherwise = Some(Box::new(next));
+            } else {
+                book.branches.insert(parse_hint(key)?, next);
+            }
+        }
+
+        Ok(book)
+    }
+
+    /// Creates a book that forces the guesses in order, whatever their hints.
+    pub fn linear(guesses: &[Craft<'static>]) -> Option<Self> {
+        guesses.iter().rev().fold(None, |next, guess| Some(Book {
+            guess: *guess,
+            branches: HashMap::new(),
+            otherwise: next.map(Box::new),
+        }))
+    }
+
+    /// Creates a book from the first `depth` guesses of a decision tree.
+    pub fn from_tree(node: &Node, depth: usize) -> Self {
+        let branches = node.branches.iter()
+            .filter(|_| depth > 1)
+            .filter_map(|branch| branch.next.as_ref().map(|next| (branch.hint, Self::from_tree(next, depth - 1))))
+            .collect();
+
+        Book { guess: node.guess, branches, otherwise: None }
+    }
+
+    /// Returns the book to follow after the guess resulted in a hint, if any.
+    pub fn next(&self, hint: &Hint) -> Option<&Book> {
+        self.branches.get(hint).or(self.otherwise.as_deref())
+    }
+
+    /// Serializes the book to JSON, in the format read by [Book::parse].
+    pub fn to_json(&self, palette: &Palette) -> Value {
+        let mut branches = self.branches.iter()
+            .sorted_by_key(|(hint, _)| **hint)
+            .map(|(hint, next)| (hint_to_string(hint), next.to_json(palette)))
+            .collect::<Map<_, _>>();
+
+        if let Some(otherwise) = &self.otherwise {
+            branches.insert(ANY_HINT.to_owned(), otherwise.to_json(palette));
+        }
+
+        let guess = self.guess.iter().map(|m| &palette.info(*m).id).collect_vec();
+
+        if branches.is_empty() {
+            json!({ "guess": guess })
+        } else {
+            json!({ "guess": guess, "branches": branches })
+        }
+    }
+}
+
+/// Converts a list of 9 item IDs to a craft.
+fn parse_ids(ids: &[Value], palette: &Palette) -> Result<Craft<'static>> {
+    let ids = ids.iter()
+        .map(|id| id.as_str().ok_or_else(|| anyhow!("expected item ID in guess, found {id}")))
+        .collect::<Result<Vec<_>>>()?;
+
+    let Ok(ids) = <[&str; 9]>::try_from(ids) else {
+        return Err(anyhow!("expected 9 item IDs in guess"));
+    };
+
+    palette.craft(ids).ok_or_else(|| anyhow!("guess {ids:?} uses materials that aren't in the palette"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::{AIR, COBBLESTONE, PLANKS, STICK}, Color};
+
+    use super::*;
+
+    #[test]
+    fn branches_on_hints() {
+        let palette = Palette::default();
+        let book = Book::parse(&json!({
+            "guess": "PPP/.k./.k.",
+            "branches": {
+                "GGG......": { "guess": "C../k../..." },
+                "YGY......": { "guess": "P../P../...", "branches": { "*": { "guess": "k../C../..." } } },
+                "*": { "guess": ["minecraft:oak_planks", "minecraft:air", "minecraft:air", "minecraft:air", "minecraft:air", "minecraft:air", "minecraft:air", "minecraft:air", "minecraft:air"] },
+            },
+        }), &palette).unwrap();
+
+        let hint = |text| parse_hint(text).unwrap();
+
+        assert_eq!(book.guess, [PLANKS, PLANKS, PLANKS, AIR, STICK, AIR, AIR, STICK, AIR]);
+        assert_eq!(book.next(&hint("GGG......")).unwrap().guess, [COBBLESTONE, AIR, AIR, STICK, AIR, AIR, AIR, AIR, AIR]);
+
+        let stick = book.next(&hint("YGY......")).unwrap();
+        assert_eq!(stick.guess, [PLANKS, AIR, AIR, PLANKS, AIR, AIR, AIR, AIR, AIR]);
+        assert_eq!(stick.next(&[Color::Gray; 9]).unwrap().guess, [STICK, AIR, AIR, COBBLESTONE, AIR, AIR, AIR, AIR, AIR]);
+
+        let otherwise = book.next(&hint("........."));
+        assert_eq!(otherwise.unwrap().guess, [PLANKS, AIR, AIR, AIR, AIR, AIR, AIR, AIR, AIR]);
+        assert!(otherwise.unwrap().next(&hint(".........")).is_none());
+
+        // Writing the book and reading it back gives the same book
+        let written = Book::parse(&book.to_json(&palette), &palette).unwrap();
+        assert_eq!(written.to_json(&palette), book.to_json(&palette));
+    }
+}
@@ -0,0 +1,106 @@
+use std::{fs, path::Path, time::{Duration, Instant}};
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+
+use crate::{matrix::{fingerprint, hash_bytes}, parse::read_json, Answer, Craft};
+
+/// How often a search writes its checkpoint.
+pub const INTERVAL: Duration = Duration::from_secs(10);
+
+/// The saved progress of a search that runs over a range of iterations.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    /// The hash of the data and parameters of the search.
+    pub config: u64,
+    /// The first iteration that hasn't been searched yet.
+    pub next: usize,
+    /// The best result found before `next`, in a format that's up to the
+    /// search.
+    pub best: Value,
+}
+
+impl Checkpoint {
+    /// Reads the checkpoint at the path, if it exists. Fails if the checkpoint
+    /// was written for a different configuration, since its progress can't be
+    /// reused.
+    pub fn load(path: &Path, config: u64) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let json = read_json(path)?;
+
+        let checkpoint = Self::parse(&json)
+            .with_context(|| format!("while parsing checkpoint from path {path:?}"))?;
+
+        if checkpoint.config != config {
+            return Err(anyhow!("checkpoint {path:?} was written for different recipes or parameters; delete it to start over"));
+        }
+
+        Ok(Some(checkpoint))
+    }
+
+    fn parse(json: &Value) -> Result<Self> {
+        let Some(config) = json.get("config").and_then(Value::as_u64) else {
+            return Err(anyhow!("expected integer at path 'config'"));
+        };
+
+        let Some(next) = json.get("next").and_then(Value::as_u64) else {
+            return Err(anyhow!("expected integer at path 'next'"));
+        };
+
+        let Some(best) = json.get("best") else {
+            return Err(anyhow!("expected value at path 'best'"));
+        };
+
+        Ok(Self { config, next: next as usize, best: best.clone() })
+    }
+
+    /// Writes the checkpoint to the path, replacing the previous one only once
+    /// it's fully written so that an interrupted write doesn't lose progress.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = json!({
+            "config": self.config,
+            "next": self.next,
+            "best": self.best,
+        });
+
+        let temporary = path.with_extension("tmp");
+
+        fs::write(&temporary, json.to_string())
+            .with_context(|| format!("while writing checkpoint {temporary:?}"))?;
+
+        fs::rename(&temporary, path)
+            .with_context(|| format!("while writing checkpoint {path:?}"))
+    }
+}
+
+/// Hashes the answers and guesses along with the name and parameters of a
+/// search, so a checkpoint is only resumed by the same search.
+pub fn config_hash(answers: &[Answer], guesses: &[Craft], search: &str, parameters: &[u64]) -> u64 {
+    let hash = hash_bytes(fingerprint(answers, guesses), search.bytes());
+
+    hash_bytes(hash, parameters.iter().flat_map(|parameter| parameter.to_le_bytes()))
+}
+
+/// Decides when a search should write its checkpoint.
+pub struct Timer(Instant);
+
+impl Timer {
+    pub fn start() -> Self {
+        Timer(Instant::now())
+    }
+
+    /// Returns whether [INTERVAL] has passed since the last checkpoint, and
+    /// restarts the timer if it has.
+    pub fn due(&mut self) -> bool {
+        let due = self.0.elapsed() >= INTERVAL;
+
+        if due {
+            self.0 = Instant::now();
+        }
+
+        due
+    }
+}
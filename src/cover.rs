@@ -0,0 +1,327 @@
+use itertools::Itertools;
+
+use crate::{palette::Palette, Answer, Craft, Material, Recipe};
+
+/// A set of materials, as a bit for each material in the palette.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MaterialSet([u64; 4]);
+
+impl MaterialSet {
+    pub fn insert(&mut self, material: Material) {
+        self.0[material.0 as usize / 64] |= 1 << (material.0 % 64);
+    }
+
+    pub fn contains(&self, material: Material) -> bool {
+        self.0[material.0 as usize / 64] & (1 << (material.0 % 64)) != 0
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        MaterialSet([0, 1, 2, 3].map(|i| self.0[i] | other.0[i]))
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        MaterialSet([0, 1, 2, 3].map(|i| self.0[i] & !other.0[i]))
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|bits| *bits == 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|bits| bits.count_ones() as usize).sum()
+    }
+
+    /// Returns every material in the set, in palette order.
+    pub fn materials(&self) -> impl Iterator<Item = Material> + '_ {
+        (0..=255u8).map(Material).filter(|material| self.contains(*material))
+    }
+}
+
+impl FromIterator<Material> for MaterialSet {
+    fn from_iter<T: IntoIterator<Item = Material>>(iter: T) -> Self {
+        let mut set = MaterialSet::default();
+        for material in iter {
+            set.insert(material);
+        }
+        set
+    }
+}
+
+/// A set of materials that a single recipe can be crafted with.
+#[derive(Debug, Clone, Copy)]
+pub struct Cover {
+    /// The position of the recipe in the list of loaded recipes.
+    pub recipe: usize,
+    pub materials: MaterialSet,
+}
+
+/// Calculates every set of materials that each recipe can be crafted with,
+/// choosing one material for each of its [Recipe::ingredients]. Recipes
+/// with an ingredient that has no materials in the palette are skipped.
+pub fn material_sets(recipes: &[(String, Recipe)]) -> Vec<Cover> {
+    recipes.iter().enumerate()
+        .flat_map(|(recipe, (_, parsed))| {
+            let ingredients = parsed.ingredients().into_iter()
+                .map(|ingredient| ingredient.iter().copied().unique().collect_vec())
+                .collect_vec();
+
+            if ingredients.iter().any(Vec::is_empty) {
+                return Vec::new();
+            }
+
+            ingredients.into_iter()
+                .multi_cartesian_product()
+                .map(|materials| materials.into_iter().collect::<MaterialSet>())
+                .unique()
+                .map(|materials| Cover { recipe, materials })
+                .collect_vec()
+        })
+        .collect_vec()
+}
+
+/// Removes duplicate sets, and sets that are a subset of another set, since a
+/// minimum cover never needs them.
+pub fn remove_subsets(covers: &[Cover]) -> Vec<Cover> {
+    let unique = covers.iter().unique_by(|cover| cover.materials).copied().collect_vec();
+
+    unique.iter()
+        .filter(|cover| !unique.iter().any(|other| other.materials != cover.materials && cover.materials.is_subset(&other.materials)))
+        .copied()
+        .collect_vec()
+}
+
+/// Covers the universe by repeatedly picking the set that covers the most
+/// materials that aren't covered yet. Returns `None` if it can't be covered.
+pub fn greedy_cover(covers: &[Cover], universe: MaterialSet) -> Option<Vec<Cover>> {
+    let mut covered = MaterialSet::default();
+    let mut chosen = Vec::new();
+
+    while !universe.is_subset(&covered) {
+        let best = covers.iter()
+            .rev()
+            .max_by_key(|cover| cover.materials.difference(&covered).len())
+            .filter(|cover| !cover.materials.difference(&covered).is_empty())?;
+
+        covered = covered.union(&best.materials);
+        chosen.push(*best);
+    }
+
+    Some(chosen)
+}
+
+/// Finds a minimum cover of the universe with a branch-and-bound search,
+/// starting from the greedy cover as the best known solution. Returns `None`
+/// if it can't be covered.
+pub fn exact_cover(covers: &[Cover], universe: MaterialSet) -> Option<Vec<Cover>> {
+    let mut best = greedy_cover(covers, universe)?;
+
+    let largest = covers.iter().map(|cover| cover.materials.len()).max().unwrap_or(1);
+
+    let mut chosen = Vec::new();
+    search(covers, universe, largest, &mut chosen, &mut best);
+
+    Some(best)
+}
+
+fn search(covers: &[Cover], uncovered: MaterialSet, largest: usize, chosen: &mut Vec<Cover>, best: &mut Vec<Cover>) {
+    if uncovered.is_empty() {
+        if chosen.len() < best.len() {
+            *best = chosen.clone();
+        }
+        return;
+    }
+
+    // Every set covers at most `largest` of the uncovered materials
+    if chosen.len() + uncovered.len().div_ceil(largest) >= best.len() {
+        return;
+    }
+
+    // Some set must cover the material with the fewest options, so only
+    // those sets need to be tried
+    let Some(options) = uncovered.materials()
+        .map(|material| covers.iter().filter(|cover| cover.materials.contains(material)).collect_vec())
+        .min_by_key(Vec::len)
+    else {
+        return;
+    };
+
+    // Try the sets that leave the fewest materials uncovered first
+    let options = options.into_iter()
+        .sorted_by_key(|cover| uncovered.difference(&cover.materials).len());
+
+    for cover in options {
+        chosen.push(*cover);
+        search(covers, uncovered.difference(&cover.materials), largest, chosen, best);
+        chosen.pop();
+    }
+}
+
+/// Runs the set cover analysis and prints the results: how many recipes use
+/// each material, and a greedy and a minimum set of recipes that together
+/// use every material that any recipe uses.
+pub fn print_cover(recipes: &[(String, Recipe)], palette: &Palette) {
+    let covers = material_sets(recipes);
+
+    let usable = covers.iter().map(|cover| cover.recipe).unique().count();
+    println!("{usable} recipes can be crafted with only the {} materials of the palette", palette.len() - 1);
+
+    println!("Recipe makeup:");
+    for material in palette.materials() {
+        let count = covers.iter().filter(|cover| cover.materials.contains(material)).count();
+        println!("    {} is in {count} material set(s)", palette.info(material).id);
+    }
+
+    let covers = remove_subsets(&covers);
+
+    let universe = covers.iter().fold(MaterialSet::default(), |set, cover| set.union(&cover.materials));
+
+    let missing = palette.materials().filter(|material| !universe.contains(*material)).collect_vec();
+    if !missing.is_empty() {
+        println!("Not used by any recipe: {}", missing.iter().map(|material| &palette.info(*material).id).join(", "));
+    }
+
+    let print = |name: &str, solution: Option<Vec<Cover>>| {
+        let Some(solution) = solution else {
+            println!("{name}: no cover found");
+            return;
+        };
+
+        println!("{name}: {} recipes", solution.len());
+        for cover in solution {
+            println!("    {}: {}", recipes[cover.recipe].0, cover.materials.materials().map(|material| palette.name(material)).join(", "));
+        }
+    };
+
+    print("Greedy cover", greedy_cover(&covers, universe));
+    print("Proven minimum cover", exact_cover(&covers, universe));
+}
+
+/// How much knowing whether each material is in the answer is worth, by
+/// material: the entropy in bits of the material being present in a random
+/// answer. Materials that are in every answer or none of them are worth
+/// nothing, and ones that are in half of them are worth the most.
+///
+/// Summing these over several materials gives an upper bound on what learning
+/// their presence reveals, not the joint information: materials that tend to
+/// appear together tell less than the sum of their entropies.
+pub fn presence_weights(answers: &[Answer]) -> [f64; 256] {
+    let mut weights = [0.0; 256];
+
+    for (index, weight) in weights.iter_mut().enumerate().skip(1) {
+        let material = Material(index as u8);
+
+        let present = answers.iter().filter(|answer| answer.craft.contains(&material)).count();
+        let p = present as f64 / answers.len().max(1) as f64;
+
+        *weight = [p, 1.0 - p].iter()
+            .filter(|p| **p > 0.0)
+            .map(|p| -p * p.log2())
+            .sum();
+    }
+
+    weights
+}
+
+/// The total weight of the materials in a set.
+pub fn weight(materials: &MaterialSet, weights: &[f64; 256]) -> f64 {
+    materials.materials().map(|material| weights[material.0 as usize]).sum()
+}
+
+/// The materials in a guess, ignoring air.
+pub fn guess_materials(guess: &Craft) -> MaterialSet {
+    guess.iter().copied().filter(|material| *material != Material::default()).collect()
+}
+
+/// Picks `k` guesses one at a time, each revealing the most weight of
+/// materials that the earlier guesses didn't already reveal.
+pub fn greedy_presence(guesses: &[Craft<'static>], weights: &[f64; 256], k: usize) -> Vec<Craft<'static>> {
+    let mut revealed = MaterialSet::default();
+    let mut chosen = Vec::new();
+
+    for _ in 0..k {
+        let Some(best) = guesses.iter()
+            .rev()
+            .max_by(|l, r| weight(&guess_materials(l).difference(&revealed), weights)
+                .total_cmp(&weight(&guess_materials(r).difference(&revealed), weights)))
+        else {
+            break;
+        };
+
+        revealed = revealed.union(&guess_materials(best));
+        chosen.push(*best);
+    }
+
+    chosen
+}
+
+/// Finds the `k` guesses that together reveal the most weight of materials,
+/// with a branch-and-bound search. Guesses with the same materials are only
+/// tried once, as the first of them.
+pub fn exact_presence(guesses: &[Craft<'static>], weights: &[f64; 256], k: usize) -> Vec<Craft<'static>> {
+    let options = guesses.iter()
+        .unique_by(|guess| guess_materials(guess))
+        .map(|guess| (*guess, guess_materials(guess)))
+        .sorted_by(|(_, l), (_, r)| weight(r, weights).total_cmp(&weight(l, weights)))
+        .collect_vec();
+
+    let greedy = greedy_presence(guesses, weights, k);
+    let mut best = (weight(&greedy.iter().map(guess_materials).fold(MaterialSet::default(), |l, r| l.union(&r)), weights), greedy);
+
+    let mut chosen = Vec::new();
+    search_presence(&options, weights, k, MaterialSet::default(), &mut chosen, &mut best);
+
+    best.1
+}
+
+fn search_presence(options: &[(Craft<'static>, MaterialSet)], weights: &[f64; 256], k: usize, revealed: MaterialSet, chosen: &mut Vec<Craft<'static>>, best: &mut (f64, Vec<Craft<'static>>)) {
+    let current = weight(&revealed, weights);
+
+    if chosen.len() == k || options.is_empty() {
+        if current > best.0 {
+            *best = (current, chosen.clone());
+        }
+        return;
+    }
+
+    for (index, (guess, materials)) in options.iter().enumerate() {
+        // The options are sorted by weight, so no later guesses can add more
+        // than this one could on its own
+        let remaining = k - chosen.len();
+        if current + remaining as f64 * weight(materials, weights) <= best.0 {
+            return;
+        }
+
+        chosen.push(*guess);
+        search_presence(&options[index + 1..], weights, k, revealed.union(materials), chosen, best);
+        chosen.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chooses_repeated_shapeless_ingredients_separately() {
+        let (a, b) = (Material(1), Material(2));
+        let recipes = [("test:shapeless".to_owned(), Recipe::Shapeless(vec![vec![a, b], vec![a, b]]))];
+
+        let sets = material_sets(&recipes).into_iter().map(|cover| cover.materials).sorted().collect_vec();
+
+        assert_eq!(sets, [[a].into_iter().collect(), [a, b].into_iter().collect(), [b].into_iter().collect()].into_iter().sorted().collect_vec());
+    }
+
+    #[test]
+    fn chooses_each_shaped_key_once() {
+        let (a, b) = (Material(1), Material(2));
+        let recipes = [("test:shaped".to_owned(), Recipe::Shaped(vec![vec![vec![a, b], vec![a, b]]]))];
+
+        let sets = material_sets(&recipes).into_iter().map(|cover| cover.materials).sorted().collect_vec();
+
+        assert_eq!(sets, [[a].into_iter().collect(), [b].into_iter().collect()].into_iter().sorted().collect_vec());
+    }
+}
@@ -0,0 +1,204 @@
+use anyhow::{Context, Result};
+use itertools::Itertools;
+use serde_json::{json, Value};
+
+use crate::{palette::Palette, parse::{filter_recipe, DataSource}, report::Format, tags::Tags};
+
+/// The number of candidate materials listed in a table.
+const LISTED_CANDIDATES: usize = 10;
+
+/// An ingredient of a recipe with no materials in the palette.
+#[derive(Debug, Clone)]
+pub struct Unknown {
+    /// How the recipe names the ingredient: item IDs, and tags prefixed with
+    /// `#`. Any of them would be accepted.
+    pub names: Vec<String>,
+    /// Every item ID that the ingredient accepts, with tags expanded.
+    pub items: Vec<String>,
+}
+
+/// A recipe that can't be crafted with the palette, and why.
+#[derive(Debug, Clone)]
+pub struct Dropped {
+    /// The name of the file the recipe was read from.
+    pub file: String,
+    /// The item ID of the result of the recipe.
+    pub result: String,
+    pub unknown: Vec<Unknown>,
+}
+
+/// The recipes dropped because they use materials missing from the palette.
+pub struct Diagnostics {
+    /// The number of shaped and shapeless recipes that were checked.
+    recipes: usize,
+    dropped: Vec<Dropped>,
+}
+
+impl Diagnostics {
+    /// Checks every shaped and shapeless recipe in the source for ingredients
+    /// with no materials in the palette. Unlike loading the recipes, tags that
+    /// can't be read are reported rather than being an error.
+    pub fn new(source: &dyn DataSource, palette: &Palette) -> Result<Self> {
+        let json = source.recipes()
+            .with_context(|| "while parsing recipe JSON")?;
+
+        let recipes = json.into_iter()
+            .filter(|(_, json)| filter_recipe(json))
+            .collect_vec();
+
+        let mut tags = Tags::new(source);
+
+        let dropped = recipes.iter()
+            .filter_map(|(file, json)| {
+                let unknown = ingredients(json).into_iter()
+                    .map(|value| resolve(value, &mut tags))
+                    .filter(|ingredient| !ingredient.items.iter().any(|id| palette.material(id).is_some()))
+                    .unique_by(|ingredient| ingredient.names.clone())
+                    .collect_vec();
+
+                if unknown.is_empty() {
+                    return None;
+                }
+
+                let result = json.get("result").and_then(|o| o.get("id")).and_then(Value::as_str).unwrap_or("unknown");
+
+                Some(Dropped { file: file.clone(), result: result.to_owned(), unknown })
+            })
+            .collect_vec();
+
+        Ok(Self { recipes: recipes.len(), dropped })
+    }
+
+    /// Every unknown item ID or tag, and how many dropped recipes use it,
+    /// most common first.
+    fn unknown_names(&self) -> Vec<(&str, usize)> {
+        self.dropped.iter()
+            .flat_map(|dropped| dropped.unknown.iter().flat_map(|unknown| &unknown.names).unique())
+            .map(String::as_str)
+            .counts()
+            .into_iter()
+            .sorted_by_key(|(name, count)| (std::cmp::Reverse(*count), *name))
+            .collect_vec()
+    }
+
+    /// Every item ID that would make at least one dropped recipe valid if it
+    /// were added to the palette, and how many recipes it would make valid,
+    /// best first.
+    fn candidates(&self) -> Vec<(&str, usize)> {
+        self.dropped.iter()
+            .flat_map(|dropped| {
+                // A recipe is only valid if every unknown ingredient accepts
+                // the item
+                let (first, rest) = dropped.unknown.split_first().expect("dropped recipes have an unknown ingredient");
+
+                first.items.iter()
+                    .unique()
+                    .filter(|id| rest.iter().all(|unknown| unknown.items.contains(id)))
+                    .map(String::as_str)
+            })
+            .counts()
+            .into_iter()
+            .sorted_by_key(|(id, count)| (std::cmp::Reverse(*count), *id))
+            .collect_vec()
+    }
+
+    /// Writes the diagnostics in the provided format. As CSV, each unknown
+    /// ingredient of each dropped recipe is a row.
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Table => self.to_table(),
+            Format::Json => serde_json::to_string_pretty(&self.to_json()).expect("diagnostics are valid JSON"),
+            Format::Csv => self.to_csv(),
+        }
+    }
+
+    fn to_table(&self) -> String {
+        let mut lines = vec![format!("Dropped recipes: {} of {}", self.dropped.len(), self.recipes)];
+
+        for dropped in &self.dropped {
+            let unknown = dropped.unknown.iter().map(|unknown| unknown.names.join(" or ")).join(", ");
+            lines.push(format!("    {} ({}): {unknown}", dropped.result, dropped.file));
+        }
+
+        lines.push(String::new());
+        lines.push("Unknown items and tags:".to_owned());
+
+        for (name, count) in self.unknown_names() {
+            lines.push(format!("    {name}: {count} recipe(s)"));
+        }
+
+        lines.push(String::new());
+
+        let candidates = self.candidates();
+
+        match candidates.first() {
+            Some((id, count)) => lines.push(format!("Adding {id} to the palette would make the most recipes valid: {count}")),
+            None => lines.push("No single material would make any recipe valid".to_owned()),
+        }
+
+        for (id, count) in candidates.iter().skip(1).take(LISTED_CANDIDATES - 1) {
+            lines.push(format!("    {id}: {count}"));
+        }
+
+        lines.join("\n")
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "recipes": self.recipes,
+            "dropped": self.dropped.iter()
+                .map(|dropped| json!({
+                    "file": dropped.file,
+                    "result": dropped.result,
+                    "unknown": dropped.unknown.iter()
+                        .map(|unknown| json!({ "names": unknown.names, "items": unknown.items }))
+                        .collect_vec(),
+                }))
+                .collect_vec(),
+            "unknown": self.unknown_names().into_iter()
+                .map(|(name, count)| json!({ "name": name, "recipes": count }))
+                .collect_vec(),
+            "candidates": self.candidates().into_iter()
+                .map(|(id, count)| json!({ "id": id, "recipes": count }))
+                .collect_vec(),
+        })
+    }
+
+    fn to_csv(&self) -> String {
+        let rows = self.dropped.iter()
+            .flat_map(|dropped| dropped.unknown.iter()
+                .map(move |unknown| format!("{},{},{}", dropped.file, dropped.result, unknown.names.join(" "))));
+
+        ["file,result,unknown".to_owned()].into_iter().chain(rows).join("\n")
+    }
+}
+
+/// Every ingredient of a recipe in JSON, as written.
+fn ingredients(json: &Value) -> Vec<&Value> {
+    match (json.get("key").and_then(Value::as_object), json.get("ingredients").and_then(Value::as_array)) {
+        (Some(key), _) => key.values().collect_vec(),
+        (None, Some(ingredients)) => ingredients.iter().collect_vec(),
+        (None, None) => Vec::new(),
+    }
+}
+
+/// Reads the names an ingredient is written with and the item IDs it
+/// accepts. Tags that can't be read accept nothing.
+fn resolve(value: &Value, tags: &mut Tags) -> Unknown {
+    match value {
+        Value::Array(values) => {
+            let resolved = values.iter().map(|value| resolve(value, tags)).collect_vec();
+
+            Unknown {
+                names: resolved.iter().flat_map(|unknown| unknown.names.clone()).collect_vec(),
+                items: resolved.into_iter().flat_map(|unknown| unknown.items).collect_vec(),
+            }
+        },
+        Value::Object(object) => match (object.get("item").and_then(Value::as_str), object.get("tag").and_then(Value::as_str)) {
+            (Some(item), _) => Unknown { names: vec![item.to_owned()], items: vec![item.to_owned()] },
+            (None, Some(tag)) => Unknown { names: vec![format!("#{tag}")], items: tags.resolve(tag).map(<[_]>::to_vec).unwrap_or_default() },
+            (None, None) => Unknown { names: vec![value.to_string()], items: Vec::new() },
+        },
+        value => Unknown { names: vec![value.to_string()], items: Vec::new() },
+    }
+}
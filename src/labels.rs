@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use crate::{permutations::offset, Answer, Craft, Placement, Recipe};
+
+/// Where a craft came from: the recipe it crafts, and which of that recipe's
+/// generated grids it is.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Origin {
+    /// The position of the recipe in the list of loaded recipes.
+    pub recipe: usize,
+    /// The position of the grid in the guesses or answers of the recipe.
+    pub variant: usize,
+}
+
+/// The names of the recipes that guesses and answers come from, so that
+/// output can say which item a grid crafts.
+pub struct Labels {
+    /// The result ID of every recipe, and whether it's shaped.
+    recipes: Vec<(String, bool)>,
+    origins: HashMap<Craft<'static>, Origin>,
+    /// The rule that decides which grids craft the answers of each recipe.
+    placements: HashMap<usize, Placement>,
+}
+
+impl Labels {
+    /// Creates labels for the recipes. Grids are looked up among the guesses
+    /// first, and then the answers.
+    pub fn new(recipes: &[(String, Recipe)], guesses: &[(Origin, Craft<'static>)], answers: &[Answer]) -> Self {
+        let recipes = recipes.iter()
+            .map(|(name, recipe)| (name.clone(), matches!(recipe, Recipe::Shaped(_))))
+            .collect();
+
+        let mut origins = HashMap::new();
+        for (origin, craft) in guesses {
+            origins.entry(*craft).or_insert(*origin);
+        }
+        for answer in answers {
+            origins.entry(answer.craft).or_insert(answer.origin);
+        }
+
+        let placements = answers.iter()
+            .map(|answer| (answer.origin.recipe, answer.placement))
+            .collect();
+
+        Self { recipes, origins, placements }
+    }
+
+    /// Returns the result ID of a recipe, without the `minecraft:` namespace.
+    pub fn name(&self, recipe: usize) -> &str {
+        let name = &self.recipes[recipe].0;
+
+        name.strip_prefix("minecraft:").unwrap_or(name)
+    }
+
+    /// Labels a grid with its recipe and variant, e.g. `piston (offset 0,0,
+    /// variant 1)`. Shapeless recipes don't have an offset.
+    pub fn label(&self, origin: Origin, craft: &Craft) -> String {
+        let name = self.name(origin.recipe);
+
+        if self.recipes[origin.recipe].1 {
+            let (x, y) = offset(craft);
+            format!("{name} (offset {x},{y}, variant {})", origin.variant)
+        } else {
+            format!("{name} (variant {})", origin.variant)
+        }
+    }
+
+    /// Labels a guess with the recipe it crafts, if it's one of the guesses or
+    /// answers.
+    pub fn guess(&self, guess: &Craft) -> String {
+        match self.origins.get(guess) {
+            Some(origin) => self.label(*origin, guess),
+            None => "unknown recipe".to_owned(),
+        }
+    }
+
+    /// Treats a grid as an answer, crafted by the same grids as the answers of
+    /// its recipe, or only by itself if its recipe is unknown. It isn't one of
+    /// the answers, so it has no index.
+    pub fn as_answer(&self, craft: &Craft) -> Answer {
+        let origin = self.origins.get(craft).copied().unwrap_or_default();
+        let placement = self.placements.get(&origin.recipe).copied().unwrap_or(Placement::Exact);
+
+        Answer { index: usize::MAX, origin, craft: *craft, placement }
+    }
+
+    /// Labels an answer with its recipe and variant.
+    pub fn answer(&self, answer: &Answer) -> String {
+        self.label(answer.origin, &answer.craft)
+    }
+}
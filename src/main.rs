@@ -1,167 +1,701 @@
+pub mod archive;
+pub mod assistant;
+pub mod book;
+pub mod checkpoint;
+pub mod cover;
+pub mod diagnostics;
+pub mod labels;
+pub mod matrix;
+pub mod notation;
+pub mod openers;
+pub mod optimal;
+pub mod palette;
 pub mod parse;
 pub mod permutations;
+pub mod report;
+pub mod scoring;
+pub mod stats;
+pub mod tags;
+pub mod tree;
 
-use std::{collections::HashMap, iter::zip};
+use std::{borrow::Cow, collections::HashMap, iter::zip, path::{Path, PathBuf}};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
 use itertools::Itertools;
-use permutations::{permutations_answer, permutations_guess};
-
+use permutations::{mirror, normalize, permutations_answer, permutations_guess, placements};
+
+use book::Book;
+use labels::{Labels, Origin};
+use matrix::{HintMatrix, PackedHint};
+use notation::to_notation;
+use palette::Palette;
+use scoring::{Scorer, ScorerKind};
 use parse::*;
 
-/// The directory containing all relevant recipes.
-/// This can be directly grabbed from Minecraft's data folder.
-pub const RECIPE_DIRECTORY: &str = "./recipe/";
+#[derive(Parser, Clone)]
+#[command(about = "Solves Minecraftle by picking the most optimal guess")]
+struct Cli {
+    /// The data root, containing the `recipe` and `tags/item` directories.
+    /// This can be directly grabbed from Minecraft's data folder, or it can be
+    /// a client jar or data pack zip.
+    #[arg(long, global = true, default_value = ".")]
+    data: PathBuf,
+
+    /// Overrides the directory containing all relevant recipes.
+    #[arg(long, global = true)]
+    recipes: Option<PathBuf>,
+
+    /// Overrides the directory containing all relevant tags.
+    #[arg(long, global = true)]
+    tags: Option<PathBuf>,
+
+    /// A named version profile to run against, either `NAME` (the data root
+    /// `<data>/NAME`) or `NAME=ROOT`. May be repeated to compare versions.
+    #[arg(long = "profile", global = true)]
+    profiles: Vec<String>,
+
+    /// Which grids the game accepts for shaped answers.
+    #[arg(long, global = true, value_enum, default_value_t = HintModel::AnyPlacement)]
+    hint_model: HintModel,
+
+    /// Also include the mirrored pattern of shaped recipes as answers.
+    /// This is only useful with the fixed hint model, since mirrored answers
+    /// are otherwise already correct.
+    #[arg(long, global = true)]
+    mirrored_answers: bool,
+
+    /// How the solvers score each possible guess.
+    #[arg(long, global = true, value_enum, default_value_t = ScorerKind::Minimax)]
+    scorer: ScorerKind,
+
+    /// Only allow guesses that are consistent with every earlier hint, like
+    /// Wordle's hard mode. Simulations also report normal mode to compare.
+    /// Commands that search for openers or optimal strategies reject it.
+    #[arg(long, global = true)]
+    hard_mode: bool,
+
+    /// The number of guesses allowed in a game. Simulations report how many
+    /// answers aren't solved within it, but only `optimal` and `book` with
+    /// `--objective failures` optimize for it.
+    #[arg(long, global = true, default_value_t = 10)]
+    guess_limit: usize,
+
+    /// A file to cache the precomputed hints of every guess against every
+    /// answer in. It's recalculated if the recipes or options change, and each
+    /// profile has its own.
+    #[arg(long, global = true)]
+    hint_cache: Option<PathBuf>,
+
+    /// An opening book of guesses to force at the start of a game, which can
+    /// branch on their hints. Defaults to the hardcoded opening guesses.
+    #[arg(long, global = true)]
+    book: Option<PathBuf>,
+
+    /// A JSON file listing the materials available in the game.
+    /// Defaults to the ingredients of Minecraftle.
+    #[arg(long, global = true)]
+    palette: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Clone)]
+enum Command {
+    /// Finds the sets of opening guesses that leave the smallest worst-case
+    /// pool, searching in parallel.
+    #[command(alias = "pairs")]
+    Openers {
+        /// The number of opening guesses in each set.
+        #[arg(short, default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..=3))]
+        k: u8,
+
+        /// A file to periodically save the progress of the search to, which
+        /// it resumes from if it already exists. Each profile has its own.
+        #[arg(long)]
+        checkpoint: Option<PathBuf>,
+    },
+    /// Finds the fewest recipes that together use every material, both
+    /// greedily and with an exact search.
+    Cover,
+    /// Finds the opening guesses that reveal the most about which materials
+    /// are in the answer, weighting each material by how often answers use it.
+    Presence {
+        /// The number of opening guesses.
+        #[arg(short, default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..=3))]
+        k: u8,
+    },
+    /// Prints statistics about the recipes, such as how often each material
+    /// is used and which recipes were dropped.
+    Stats {
+        /// How the statistics are written.
+        #[arg(long, value_enum, default_value_t = report::Format::Table)]
+        format: report::Format,
+
+        /// The file to write the statistics to, instead of standard output.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Lists the recipes that were dropped because they use materials
+    /// missing from the palette, and which material to add to keep the most.
+    Diagnose {
+        /// How the diagnostics are written.
+        #[arg(long, value_enum, default_value_t = report::Format::Table)]
+        format: report::Format,
+
+        /// The file to write the diagnostics to, instead of standard output.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Runs the greedy algorithm against a single answer.
+    Solve {
+        /// The index of the answer to solve for.
+        answer: usize,
+    },
+    /// Runs the modified greedy algorithm against every answer and prints statistics.
+    Simulate {
+        /// Don't follow the opening book.
+        #[arg(long)]
+        no_opener: bool,
+    },
+    /// Runs the greedy algorithm against an adversarial game.
+    Adversarial,
+    /// Suggests guesses for a real game, reading each hint from standard
+    /// input. Other guesses can be entered, and guesses can be undone.
+    Interactive,
+    /// Reports the average and worst-case guess counts of the modified greedy
+    /// algorithm over every answer for each scorer.
+    Benchmark {
+        /// Don't follow the opening book.
+        #[arg(long)]
+        no_opener: bool,
+    },
+    /// Runs a strategy against every answer and reports a histogram of guess
+    /// counts, per-recipe results, the worst cases, and the runtime.
+    Report {
+        /// The strategy to run.
+        #[arg(long, value_enum, default_value_t = report::Strategy::ModifiedGreedy)]
+        strategy: report::Strategy,
+
+        /// Don't follow the opening book.
+        #[arg(long)]
+        no_opener: bool,
+
+        /// How the report is written.
+        #[arg(long, value_enum, default_value_t = report::Format::Table)]
+        format: report::Format,
+
+        /// The file to write the report to, instead of standard output.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Searches for the optimal strategy like `optimal`, and writes its first
+    /// guesses as an opening book.
+    Book {
+        /// What the strategy minimizes.
+        #[arg(long, value_enum, default_value_t = optimal::Objective::Average)]
+        objective: optimal::Objective,
+
+        /// Only try this many of the best guesses at each step.
+        #[arg(long)]
+        width: Option<usize>,
+
+        /// The number of guesses to include in the book.
+        #[arg(long, default_value_t = 2)]
+        depth: usize,
+
+        /// The file to write the book to, instead of standard output.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Searches for the strategy that provably minimizes the average or
+    /// worst-case number of guesses, or the number of answers not solved
+    /// within the guess limit, and writes its decision tree as JSON.
+    Optimal {
+        /// What the strategy minimizes.
+        #[arg(long, value_enum, default_value_t = optimal::Objective::Average)]
+        objective: optimal::Objective,
+
+        /// Only try this many of the best guesses (according to the scorer) at
+        /// each step. This is much faster, but the result is no longer proven.
+        #[arg(long)]
+        width: Option<usize>,
+
+        /// The file to write the tree to, instead of standard output.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Builds the full decision tree of the modified greedy algorithm and
+    /// writes it as JSON.
+    Tree {
+        /// Don't follow the opening book.
+        #[arg(long)]
+        no_opener: bool,
+
+        /// The file to write the tree to, instead of standard output.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+impl Command {
+    /// The file that the command writes its output or progress to, if it can
+    /// write to one.
+    fn file_mut(&mut self) -> Option<&mut Option<PathBuf>> {
+        match self {
+            Command::Openers { checkpoint, .. } => Some(checkpoint),
+            Command::Diagnose { output, .. }
+            | Command::Stats { output, .. }
+            | Command::Report { output, .. }
+            | Command::Book { output, .. }
+            | Command::Optimal { output, .. }
+            | Command::Tree { output, .. } => Some(output),
+            _ => None,
+        }
+    }
 
-/// The directory containing all relevant tags.
-/// This can be directly grabbed from Minecraft's data folder.
-pub const TAG_DIRECTORY: &str = "./tags/item/";
+    /// Whether the command follows `--hard-mode`.
+    fn supports_hard_mode(&self) -> bool {
+        match self {
+            Command::Solve { .. }
+            | Command::Simulate { .. }
+            | Command::Adversarial
+            | Command::Interactive
+            | Command::Benchmark { .. }
+            | Command::Tree { .. } => true,
+            Command::Report { strategy, .. } => *strategy != report::Strategy::Optimal,
+            Command::Openers { .. }
+            | Command::Cover
+            | Command::Presence { .. }
+            | Command::Stats { .. }
+            | Command::Diagnose { .. }
+            | Command::Book { .. }
+            | Command::Optimal { .. } => false,
+        }
+    }
+}
 
 fn main() -> Result<()> {
+    let cli = Cli::parse();
 
-    let files = list_dir(RECIPE_DIRECTORY)
-        .with_context(|| "while listing files in recipe directory")?;
-
-    let json = files.into_iter()
-        .map(|path| match read_json(&path) {
-            Ok(json) => Ok((path, json)),
-            Err(err) => Err(err.context(format!("while parsing path {path:?}")))
-        })
-        .collect::<Result<Vec<_>>>()
-        .with_context(|| "while parsing recipe JSON")?;
-    
-    println!("{} total recipes", json.len());
-
-    let recipes = json.into_iter()
-        .filter(|(_, json)| filter_recipe(json))
-        .map(|(path, json)| parse_recipe(json)
-            .with_context(|| format!("while parsing path {path:?}"))
-        )
-        .collect::<Result<Vec<_>, _>>()
-        .with_context(|| "while parsing recipes from JSON")?;
-
-    // Normally we would have to filter out recipes here that have ingredients
-    // with 0 materials, but this is not an issue as the iterated Cartesian
-    // product of them will result in a 0-length list anyway.
-    
-    println!("{} relevant recipes (shaped or shapeless)", recipes.len());
-
-    let recipes = recipes.into_iter()
-        .filter(|(_, recipe)| matches!(recipe, Recipe::Shaped(_)))
-        .collect_vec();
+    if cli.hard_mode && !cli.command.supports_hard_mode() {
+        return Err(anyhow!("--hard-mode is only supported by solve, simulate, adversarial, interactive, benchmark, tree, and report without --strategy optimal"));
+    }
+
+    let palette = match &cli.palette {
+        Some(path) => Palette::load(path)?,
+        None => Palette::default(),
+    };
+
+    let profiles = if cli.profiles.is_empty() {
+        vec![Profile::new("default", Source::from_root(&cli.data))]
+    } else {
+        cli.profiles.iter().map(|spec| Profile::parse(spec, &cli.data)).collect_vec()
+    };
+
+    let several = profiles.len() > 1;
+
+    for mut profile in profiles {
+        // Each profile writes to its own file, so they don't overwrite each other
+        let mut cli = cli.clone();
+        if several {
+            if let Some(Some(path)) = cli.command.file_mut() {
+                *path = profile_path(path, &profile.name);
+            }
+            if let Some(path) = &mut cli.hint_cache {
+                *path = profile_path(path, &profile.name);
+            }
+        }
+
+        if let Source::Directory(config) = &mut profile.source {
+            if let Some(recipes) = &cli.recipes {
+                config.recipes = recipes.clone();
+            }
+            if let Some(tags) = &cli.tags {
+                config.tags = tags.clone();
+            }
+        }
+
+        eprintln!("Profile {}", profile.name);
 
-    println!("{} filtered and relevant recipes (removed shapeless)", recipes.len());
+        let source = profile.source.open()
+            .with_context(|| format!("while opening profile {}", profile.name))?;
 
-    let guesses = recipes.iter()
-        .map(|r| permutations_guess(&r.1))
-        .flatten()
+        run(&cli, source.as_ref(), &palette)
+            .with_context(|| format!("while running profile {}", profile.name))?;
+    }
+
+    Ok(())
+}
+
+/// Runs the command against the recipes loaded from the provided source.
+fn run(cli: &Cli, source: &dyn DataSource, palette: &Palette) -> Result<()> {
+    // The diagnostics read the recipes themselves, so that tags that fail to
+    // load are reported too
+    if let Command::Diagnose { format, ref output } = cli.command {
+        let diagnostics = diagnostics::Diagnostics::new(source, palette)?;
+
+        return write_output(&diagnostics.render(format), output.as_ref(), "diagnostics");
+    }
+
+    let recipes = load_recipes(source, palette)?;
+
+    // The set cover only needs the recipes
+    if let Command::Cover = cli.command {
+        cover::print_cover(&recipes, palette);
+        return Ok(());
+    }
+
+    let labelled = recipes.iter().enumerate()
+        .flat_map(|(recipe, r)| permutations_guess(&r.1).into_iter().enumerate()
+            .map(move |(variant, craft)| (Origin { recipe, variant }, craft)))
         .collect_vec();
 
-    let answers = recipes.iter()
-        .map(|r| permutations_answer(&r.1, get_shaped_offset))
-        .flatten()
+    let guesses = labelled.iter().map(|(_, craft)| *craft).collect_vec();
+
+    let answers = recipes.iter().enumerate()
+        .flat_map(|(recipe, r)| permutations_answer(&r.1, get_shaped_offset, get_shapeless_slots, cli.mirrored_answers).into_iter()
+            .map(move |answer| Answer { origin: Origin { recipe, ..answer.origin }, ..answer }))
+        .enumerate()
+        .map(|(index, answer)| cli.hint_model.apply(Answer { index, ..answer }))
         .collect_vec();
 
-    println!("{} total recipe guesses; {} total recipe answers", guesses.len(), answers.len());
+    let labels = Labels::new(&recipes, &labelled, &answers);
+
+    eprintln!("{} total recipe guesses; {} total recipe answers", guesses.len(), answers.len());
+
+    // The statistics don't need the hint matrix
+    if let Command::Stats { format, ref output } = cli.command {
+        let stats = stats::Stats::new(&recipes, &answers, &guesses, palette);
+
+        return write_output(&stats.render(format, palette, &labels), output.as_ref(), "statistics");
+    }
+
+    let matrix = match &cli.hint_cache {
+        Some(path) => HintMatrix::cached(path, &answers, &guesses)?,
+        None => HintMatrix::new(&answers, &guesses),
+    };
+
+    let book = match &cli.book {
+        Some(path) => Some(Book::load(path, palette)?),
+        None => Book::linear(&default_opener(palette)),
+    };
+
+    let ctx = SolverContext { palette, scorer: cli.scorer.scorer(), matrix: &matrix, labels: &labels, hard_mode: cli.hard_mode };
+
+    match cli.command {
+        Command::Openers { k, ref checkpoint } => {
+            let found = openers::best_openers(&answers, &guesses, &matrix, k as usize, checkpoint.as_deref())?;
+
+            for rows in found.openers {
+                println!("{} from {}", found.max_pool, rows.iter().map(|row| describe(&guesses[*row], ctx)).join(", "));
+            }
+        },
+        Command::Cover => unreachable!("the set cover is handled before the answers are generated"),
+        Command::Diagnose { .. } => unreachable!("the diagnostics are handled before the recipes are loaded"),
+        Command::Stats { .. } => unreachable!("the statistics are handled before the hint matrix is built"),
+        Command::Presence { k } => {
+            let k = k as usize;
+            let weights = cover::presence_weights(&answers);
+
+            for (name, chosen) in [("Greedy", cover::greedy_presence(&guesses, &weights, k)), ("Best", cover::exact_presence(&guesses, &weights, k))] {
+                let revealed = chosen.iter().map(cover::guess_materials).fold(cover::MaterialSet::default(), |l, r| l.union(&r));
+
+                // The hints that the guesses actually split the answers into
+                let rows = chosen.iter().filter_map(|guess| guesses.iter().position(|g| g == guess)).collect_vec();
+                let pools = answers.iter().counts_by(|answer| rows.iter().map(|row| matrix.get(*row, answer)).collect_vec());
+
+                println!("{name}: {:.4} bits of material presence, {} pools, largest {}",
+                    cover::weight(&revealed, &weights), pools.len(), pools.values().max().unwrap_or(&0));
+                for guess in chosen {
+                    println!("    {}", describe(&guess, ctx));
+                }
+            }
+        },
+        Command::Solve { answer } => {
+            let Some(answer) = answers.get(answer) else {
+                return Err(anyhow!("answer index {answer} is out of bounds (there are {} answers)", answers.len()));
+            };
+
+            println!("Guesses: {}", greedy_algorithm_against_answer(&answers, &guesses, ctx, answer));
+        },
+        Command::Simulate { no_opener } => {
+            let book = if no_opener { None } else { book.as_ref() };
+
+            let modes = if cli.hard_mode { vec![false, true] } else { vec![false] };
+
+            for hard_mode in modes {
+                let ctx = SolverContext { hard_mode, ..ctx };
+
+                let raw = answers.iter()
+                    .map(|answer| modified_greedy(&answers, &guesses, ctx, book, |guess| calculate_hint(answer, guess)))
+                    .collect_vec();
+
+                let average = raw.iter().sum::<usize>() as f64 / (answers.len() as f64);
+                let min = raw.iter().min().unwrap_or(&0);
+                let max = raw.iter().max().unwrap_or(&0);
+
+                let mode = if hard_mode { "hard mode: " } else { "" };
+
+                println!("{mode}average: {}, min: {}, max: {}", average, min, max);
 
-    // 255C2 calculator
-    let min = guesses.iter().combinations(2)
-        .map(|vec| {
-            let mut hint_map = HashMap::new();
+                let greedy = answers.iter()
+                    .map(|answer| greedy_algorithm_against_answer(&answers, &guesses, ctx, answer))
+                    .collect_vec();
 
-            for answer in &answers {
-                let hints = vec.iter().map(|guess| calculate_hint(answer, guess)).collect_vec();
+                let adversarial = if greedy_adversarial(&answers, &guesses, ctx).len() > cli.guess_limit { "fails" } else { "solves" };
 
-                *hint_map.entry(hints).or_insert(0) += 1;
+                println!("{mode}failures within {} guesses: greedy: {}, modified greedy: {}, adversarial: {adversarial}",
+                    cli.guess_limit, failures(&greedy, cli.guess_limit), failures(&raw, cli.guess_limit));
             }
-            
-            ((vec), *hint_map.values().max().unwrap())
-        }).min_set_by_key(|(_, v)| *v);
-
-    for (vec, count) in min {
-        println!("{} from [{}]", count, vec.iter().map(|v| fmt(v)).join("], ["));
-    }
-
-
-    // Greedy algorithm stuff
-    // println!("Guesses: {}", greedy_algorithm_against(&answers, &guesses, guess_from_user));
-
-    // let data = answers.iter()
-    //     .map(|answer| (answer, modified_greedy(&answers, &guesses, &[
-    //         [
-    //             Material::Planks, Material::Planks, Material::Planks,
-    //             Material::Cobblestone, Material::IronIngot, Material::Cobblestone,
-    //             Material::Cobblestone, Material::Redstone, Material::Cobblestone,
-    //         ],
-    //         [
-    //             Material::GoldIngot, Material::GoldIngot, Material::GoldIngot,
-    //             Material::Air, Material::Stick, Material::Air,
-    //             Material::Air, Material::Stick, Material::Air,
-    //         ],
-    //         [
-    //             Material::Air, Material::RedstoneTorch, Material::Air,
-    //             Material::RedstoneTorch, Material::Quartz, Material::RedstoneTorch,
-    //             Material::Stone, Material::Stone, Material::Stone,
-    //         ],
-    //     ], |guess| {
-    //         // println!("{}", fmt(guess));
-    //         calculate_hint(&answer, guess)
-    //     }))).collect_vec();
-
-    // let raw = data.iter().map(|(_, v)| *v).collect_vec();
-
-    // let average = raw.iter().sum::<usize>() as f64 / (answers.len() as f64);
-    // let min = raw.iter().min().unwrap();
-    // let max = raw.iter().max().unwrap();
-
-    // println!("average: {}, min: {}, max: {}", average, min, max);
+        },
+        Command::Adversarial => {
+            let steps = greedy_adversarial(&answers, &guesses, ctx);
+
+            for (before, after, guess) in &steps {
+                println!("From {before} to {after} possible solutions by {}", describe(guess, ctx));
+            }
+
+            println!("Guesses: {}", steps.len());
+        },
+        Command::Interactive => assistant::assist(&answers, &guesses, ctx)?,
+        Command::Benchmark { no_opener } => {
+            let book = if no_opener { None } else { book.as_ref() };
+
+            // The same strategy as `simulate`, so their averages agree
+            for kind in ScorerKind::value_variants() {
+                let ctx = SolverContext { scorer: kind.scorer(), ..ctx };
+
+                let counts = answers.iter()
+                    .map(|answer| modified_greedy(&answers, &guesses, ctx, book, |guess| calculate_hint(answer, guess)))
+                    .collect_vec();
+
+                let average = counts.iter().sum::<usize>() as f64 / (counts.len() as f64);
+                let max = counts.iter().max().unwrap_or(&0);
+
+                println!("{:?}: average: {}, max: {}, failures: {}", kind, average, max, failures(&counts, cli.guess_limit));
+            }
+        },
+        Command::Report { strategy, no_opener, format, ref output } => {
+            let book = if no_opener { None } else { book.as_ref() };
+
+            let report = report::Report::run(strategy, &answers, &guesses, ctx, book, cli.guess_limit);
+
+            write_output(&report.render(format, ctx), output.as_ref(), "report")?;
+        },
+        Command::Book { objective, width, depth, ref output } => {
+            let mut search = optimal::Search::new(&answers, &guesses, ctx, objective, width, cli.guess_limit);
+            search.solve();
+
+            let book = Book::from_tree(&search.tree(), depth);
+
+            write_output(&serde_json::to_string_pretty(&book.to_json(palette))?, output.as_ref(), "opening book")?;
+        },
+        Command::Optimal { objective, width, ref output } => {
+            let mut search = optimal::Search::new(&answers, &guesses, ctx, objective, width, cli.guess_limit);
+
+            let cost = search.solve();
+            let proven = if width.is_some() { "best found" } else { "proven minimum" };
+
+            match objective {
+                optimal::Objective::Average => eprintln!("{proven}: {} total guesses, average: {}", cost, cost as f64 / answers.len() as f64),
+                optimal::Objective::WorstCase => eprintln!("{proven}: {} guesses in the worst case", cost),
+                optimal::Objective::Failures => eprintln!("{proven}: {} of {} answers not solved within {} guesses, solve probability: {}",
+                    cost, answers.len(), cli.guess_limit, 1.0 - cost as f64 / answers.len() as f64),
+            }
+
+            write_tree(&search.tree(), ctx, output.as_ref())?;
+        },
+        Command::Tree { no_opener, ref output } => {
+            let book = if no_opener { None } else { book.as_ref() };
+
+            let tree = tree::build_tree(&answers, &guesses, ctx, book);
+
+            let counts = tree.guess_counts();
+            let average = counts.iter().sum::<usize>() as f64 / (counts.len() as f64);
+
+            eprintln!("average: {}, max: {}", average, counts.iter().max().unwrap_or(&0));
+
+            write_tree(&tree, ctx, output.as_ref())?;
+        },
+    }
 
     Ok(())
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
-pub enum Material {
-    Air,
-    Planks,
-    Cobblestone,
-    Stone,
-    Glass,
-    Wool,
-    Stick,
-    Coal,
-    Diamond,
-    GoldIngot,
-    IronIngot,
-    Redstone,
-    Quartz,
-    Slab,
-    Log,
-    IronNugget,
-    RedstoneTorch,
-    String,
-    Leather,
+/// Formats the number of guess counts over the limit, and their fraction.
+fn failures(counts: &[usize], limit: usize) -> String {
+    let failed = counts.iter().filter(|count| **count > limit).count();
+
+    format!("{failed}/{} ({:.1}%)", counts.len(), 100.0 * failed as f64 / counts.len().max(1) as f64)
+}
+
+/// Writes a decision tree as JSON to the provided file, or standard output.
+fn write_tree(tree: &tree::Node, ctx: SolverContext, output: Option<&PathBuf>) -> Result<()> {
+    let json = serde_json::to_string_pretty(&tree.to_json(ctx.palette, ctx.labels))?;
+
+    write_output(&json, output, "tree")
 }
 
-impl Default for Material {
-    fn default() -> Self {
-        Material::Air
+/// Adds the name of a profile to a file name, before its extension, e.g.
+/// `report.json` to `report.1.21.json`.
+fn profile_path(path: &Path, profile: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    match path.extension() {
+        Some(extension) => path.with_file_name(format!("{stem}.{profile}.{}", extension.to_string_lossy())),
+        None => path.with_file_name(format!("{stem}.{profile}")),
     }
 }
 
+/// Writes the output of a command to the provided file, or standard output.
+fn write_output(text: &str, output: Option<&PathBuf>, what: &str) -> Result<()> {
+    match output {
+        Some(path) => std::fs::write(path, text)
+            .with_context(|| format!("while writing {what} to {path:?}")),
+        None => {
+            println!("{text}");
+            Ok(())
+        },
+    }
+}
+
+/// The hardcoded opening guesses used when no opening book is provided.
+/// Guesses that use materials missing from the palette are skipped.
+fn default_opener(palette: &Palette) -> Vec<Craft<'static>> {
+    [
+        [
+            "minecraft:oak_planks", "minecraft:oak_planks", "minecraft:oak_planks",
+            "minecraft:cobblestone", "minecraft:iron_ingot", "minecraft:cobblestone",
+            "minecraft:cobblestone", "minecraft:redstone", "minecraft:cobblestone",
+        ],
+        [
+            "minecraft:gold_ingot", "minecraft:gold_ingot", "minecraft:gold_ingot",
+            "minecraft:air", "minecraft:stick", "minecraft:air",
+            "minecraft:air", "minecraft:stick", "minecraft:air",
+        ],
+        [
+            "minecraft:air", "minecraft:redstone_torch", "minecraft:air",
+            "minecraft:redstone_torch", "minecraft:quartz", "minecraft:redstone_torch",
+            "minecraft:stone", "minecraft:stone", "minecraft:stone",
+        ],
+    ].into_iter().filter_map(|ids| palette.craft(ids)).collect_vec()
+}
+
+/// Everything the solvers need besides the answers and guesses.
+#[derive(Clone, Copy)]
+pub struct SolverContext<'a> {
+    pub palette: &'a Palette,
+    pub scorer: &'a dyn Scorer,
+    pub matrix: &'a HintMatrix,
+    pub labels: &'a Labels,
+    /// Whether each guess must be consistent with every earlier hint.
+    pub hard_mode: bool,
+}
+
+/// A material, stored as its index in the [Palette].
+/// The default material, with index 0, is always air.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct Material(pub u8);
+
 #[derive(Debug)]
 pub enum Recipe {
     Shaped(Vec<Vec<Ingredient>>),
     Shapeless(Vec<Ingredient>),
 }
 
+impl Recipe {
+    /// Returns every ingredient whose material can be chosen independently,
+    /// ignoring air. A shaped recipe's ingredient is only listed once however
+    /// many slots use it, like a key of its pattern, while every ingredient of
+    /// a shapeless recipe is listed.
+    pub fn ingredients(&self) -> Vec<&Ingredient> {
+        let air = [Material::default()];
+
+        match self {
+            Recipe::Shaped(grid) => grid.iter().flatten().filter(|ingredient| **ingredient != air).unique().collect_vec(),
+            Recipe::Shapeless(ingredients) => ingredients.iter().filter(|ingredient| **ingredient != air).collect_vec(),
+        }
+    }
+}
+
 pub type Ingredient = Vec<Material>;
 
 pub type Craft<'a> = [Material; 9];
 
+/// A possible answer to the game.
+/// This is a recipe, so every grid that crafts it is correct, not just the
+/// grid it's placed as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Answer {
+    /// The position of the answer in the list of every answer.
+    pub index: usize,
+    /// The recipe and variant the answer comes from.
+    pub origin: Origin,
+    /// The grid the answer is placed as.
+    pub craft: Craft<'static>,
+    /// Which other grids also craft the answer.
+    pub placement: Placement,
+}
+
+/// The rule that decides which grids craft an answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Placement {
+    /// Only the exact grid is correct.
+    Exact,
+    /// The grid may be moved to any offset and mirrored left-to-right.
+    Shaped,
+    /// The materials may be arranged in any way.
+    Shapeless,
+}
+
+impl Answer {
+    /// Determines whether the provided guess crafts this answer.
+    pub fn accepts(&self, guess: &Craft) -> bool {
+        match self.placement {
+            Placement::Exact => self.craft == *guess,
+            Placement::Shaped => {
+                let guess = normalize(guess);
+
+                guess == normalize(&self.craft) || guess == normalize(&mirror(&self.craft))
+            },
+            Placement::Shapeless => {
+                let mut answer = self.craft;
+                let mut guess = *guess;
+
+                answer.sort();
+                guess.sort();
+
+                answer == guess
+            },
+        }
+    }
+}
+
+/// Which grids the game accepts for shaped answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum HintModel {
+    /// Shaped answers are only correct at the offset they're placed at.
+    Fixed,
+    /// Shaped answers are correct at any offset and mirrored, like in the game.
+    AnyPlacement,
+}
+
+impl HintModel {
+    /// Applies this model to an answer.
+    fn apply(self, answer: Answer) -> Answer {
+        match (self, answer.placement) {
+            (HintModel::Fixed, Placement::Shaped) => Answer { placement: Placement::Exact, ..answer },
+            _ => answer,
+        }
+    }
+}
+
 pub type Hint = [Color; 9];
 
 // I tried naming these something other than the color but they were too verbose
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Color {
     Gray, Yellow, Green
 }
@@ -181,59 +715,43 @@ fn get_shaped_offset(size: (usize, usize)) -> (usize, usize) {
     }
 }
 
-fn guess_from_user(guess: &Craft) -> Hint {
-    println!("Guess is {}", fmt(guess));
-
-    let mut input_text = String::new();
-    std::io::stdin()
-        .read_line(&mut input_text)
-        .expect("failed to read from stdin");
-
-    let mut colors = [Color::Gray; 9];
-    
-    for (index, color) in input_text.chars().enumerate() {
-        if index >= 9 { break; }
-
-        colors[index] = match color {
-            'G' => Color::Green,
-            'Y' => Color::Yellow,
-            _ => Color::Gray,
-        }
+/// The slots that the ingredients of a shapeless answer are placed in.
+/// A single ingredient is placed in the center, like a 1x1 shaped recipe, and
+/// any more fill the grid in reading order.
+fn get_shapeless_slots(count: usize) -> Vec<usize> {
+    match count {
+        1 => vec![4],
+        count => (0..count).collect(),
     }
-
-    colors
 }
 
-
-fn fmt(guess: &Craft) -> String {
-    guess.iter().map(|v| format!("{:?}", v)).collect_vec().join(" ")
+/// Formats a guess along with the recipe it crafts, e.g.
+/// `piston (offset 0,0, variant 0): PPP/CiC/CrC`.
+fn describe(guess: &Craft, ctx: SolverContext) -> String {
+    format!("{}: {}", ctx.labels.guess(guess), to_notation(guess, ctx.palette))
 }
 
 /// Simulates the greedy algorithm against the provided answer.
-fn greedy_algorithm_against_answer(answers: &Vec<Craft>, guesses: &Vec<Craft>, answer: &Craft) -> usize {
-    greedy_algorithm_against(&answers, &guesses, |guess| {
-        // println!("{}", fmt(guess));
-        calculate_hint(&answer, guess)
-    })
+fn greedy_algorithm_against_answer(answers: &[Answer], guesses: &[Craft], ctx: SolverContext, answer: &Answer) -> usize {
+    greedy_algorithm_against(answers, guesses, ctx, |guess| calculate_hint(answer, guess))
 }
 
 /// Calculates the guess that will result in the next guess specifically gaining
-/// the most amount of information.
-fn most_information<'a>(answers: &'a Vec<Craft>, guesses: &'a Vec<Craft>) -> Vec<(Vec<usize>, &'a Craft<'a>)> {
-    let mut best_guesses = guesses.iter()
-        .map(|guess| (assemble_pools(&guess, &answers).values().map(|v| v.len()).sorted().rev().collect_vec(), guess))
-        .min_set_by_key(|(values, _)| values[0]);
-
-    for index in 1..best_guesses.iter().map(|(_, r)| r.len()).max().unwrap_or(0) {
-        best_guesses = best_guesses.into_iter().min_set_by_key(|(values, _)| values.get(index).cloned().unwrap_or(0));
-    }
-
-    best_guesses
+/// the most amount of information, as decided by the scorer.
+/// Each guess is returned alongside its pool sizes, sorted from largest to
+/// smallest.
+fn most_information<'a>(answers: &'a [Answer], guesses: &'a [Craft], ctx: SolverContext) -> Vec<(Vec<usize>, &'a Craft<'a>)> {
+    guesses.iter()
+        .map(|guess| (assemble_pools(guess, answers, ctx.matrix).values().map(|v| v.len()).sorted().rev().collect_vec(), guess))
+        .min_set_by_key(|(values, _)| ctx.scorer.score(values))
 }
 
 /// Simulates a greedy algorithm against the provided guess function.
-fn modified_greedy<F: Fn(&Craft) -> Hint>(answers: &Vec<Craft>, guesses: &Vec<Craft>, hardcoded: &[Craft], try_guess: F) -> usize {
-    let best_guesses = most_information(answers, guesses);
+/// Guesses are forced by the opening book until it runs out.
+fn modified_greedy<F: Fn(&Craft) -> Hint>(answers: &[Answer], guesses: &[Craft], ctx: SolverContext, book: Option<&Book>, try_guess: F) -> usize {
+    let best_guesses = most_information(answers, guesses, ctx);
+
+    let mut tried: Vec<(&Craft, Hint)> = Vec::new();
 
     if best_guesses.iter().any(|v| v.0.len() == 1) {
 
@@ -241,6 +759,10 @@ fn modified_greedy<F: Fn(&Craft) -> Hint>(answers: &Vec<Craft>, guesses: &Vec<Cr
 
         let mut count = 0;
         for guess in &best_guesses {
+            if !is_allowed(guess.1, &tried, ctx) {
+                continue;
+            }
+
             let result = try_guess(guess.1);
             count += 1;
 
@@ -248,14 +770,12 @@ fn modified_greedy<F: Fn(&Craft) -> Hint>(answers: &Vec<Craft>, guesses: &Vec<Cr
             if result == [Color::Green; 9] {
                 return count;
             }
+
+            tried.push((guess.1, result));
         }
     }
 
-    let mut best_guess = best_guesses.get(0).unwrap().1;
-
-    if hardcoded.len() > 0 {
-        best_guess = &hardcoded[0];
-    }
+    let best_guess = choose_guess(&best_guesses, book, answers, &tried, ctx);
 
     let result = try_guess(best_guess);
     // println!("Guessed {:?}; result was {:?}", lowest_pair.1.iter().map(|v| &v["minecraft:".len()..]).collect_vec(), result);
@@ -265,24 +785,26 @@ fn modified_greedy<F: Fn(&Craft) -> Hint>(answers: &Vec<Craft>, guesses: &Vec<Cr
         return 1; // Took one guess
     }
 
-    let pools = assemble_pools(best_guess, &answers);
-    let new_answers = pools.get(&result).unwrap();
+    let pools = assemble_pools(best_guess, answers, ctx.matrix);
+    let new_answers = pools.get(&PackedHint::pack(&result)).unwrap();
 
     if new_answers.len() == 1 {
         // println!("Fast exiting with one guess left: {:?}", new_answers.get(0).unwrap().iter().map(|v| &v["minecraft:".len()..]).collect_vec());
-        println!("{}", fmt(new_answers.get(0).unwrap()));
         // 1 for initial guess + 1 for now
         return 1 + 1;
     }
 
     // println!("Simulating guess deeper...");
-    let new_hardcoded = if hardcoded.len() == 0 { hardcoded } else { &hardcoded[1..] };
-    return 1 + modified_greedy(new_answers, guesses, new_hardcoded, try_guess);
+    let guesses = next_guesses(guesses, new_answers, ctx, best_guess, &result);
+    let book = follow_book(book, &result, &guesses, ctx);
+    1 + modified_greedy(new_answers, &guesses, ctx, book, try_guess)
 }
 
 /// Simulates a greedy algorithm against the provided guess function.
-fn greedy_algorithm_against<F: Fn(&Craft) -> Hint>(answers: &Vec<Craft>, guesses: &Vec<Craft>, try_guess: F) -> usize {
-    let best_guesses = most_information(answers, guesses);
+fn greedy_algorithm_against<F: Fn(&Craft) -> Hint>(answers: &[Answer], guesses: &[Craft], ctx: SolverContext, try_guess: F) -> usize {
+    let best_guesses = most_information(answers, guesses, ctx);
+
+    let mut tried: Vec<(&Craft, Hint)> = Vec::new();
 
     if best_guesses.iter().any(|v| v.0.len() == 1) {
 
@@ -290,6 +812,10 @@ fn greedy_algorithm_against<F: Fn(&Craft) -> Hint>(answers: &Vec<Craft>, guesses
 
         let mut count = 0;
         for guess in &best_guesses {
+            if !is_allowed(guess.1, &tried, ctx) {
+                continue;
+            }
+
             let result = try_guess(guess.1);
             count += 1;
 
@@ -297,12 +823,14 @@ fn greedy_algorithm_against<F: Fn(&Craft) -> Hint>(answers: &Vec<Craft>, guesses
             if result == [Color::Green; 9] {
                 return count;
             }
+
+            tried.push((guess.1, result));
         }
     }
 
-    let lowest_pair = best_guesses.get(0).unwrap();
+    let best_guess = choose_guess(&best_guesses, None, answers, &tried, ctx);
 
-    let result = try_guess(lowest_pair.1);
+    let result = try_guess(best_guess);
     // println!("Guessed {:?}; result was {:?}", lowest_pair.1.iter().map(|v| &v["minecraft:".len()..]).collect_vec(), result);
 
     if result == [Color::Green; 9] {
@@ -310,50 +838,116 @@ fn greedy_algorithm_against<F: Fn(&Craft) -> Hint>(answers: &Vec<Craft>, guesses
         return 1; // Took one guess
     }
 
-    let pools = assemble_pools(lowest_pair.1, &answers);
-    let new_answers = pools.get(&result).unwrap();
+    let pools = assemble_pools(best_guess, answers, ctx.matrix);
+    let new_answers = pools.get(&PackedHint::pack(&result)).unwrap();
 
     if new_answers.len() == 1 {
         // println!("Fast exiting with one guess left: {:?}", new_answers.get(0).unwrap().iter().map(|v| &v["minecraft:".len()..]).collect_vec());
-        println!("{}", fmt(new_answers.get(0).unwrap()));
         // 1 for initial guess + 1 for now
         return 1 + 1;
     }
 
     // println!("Simulating guess deeper...");
-    return 1 + greedy_algorithm_against(new_answers, guesses, try_guess);
+    let guesses = next_guesses(guesses, new_answers, ctx, best_guess, &result);
+    1 + greedy_algorithm_against(new_answers, &guesses, ctx, try_guess)
+}
+
+/// Chooses the next guess: the book's guess if there is one, and otherwise the
+/// best guess. Only allowed guesses are chosen, so in hard mode the first
+/// allowed best guess is chosen, or the first answer that's still possible if
+/// none of them are, since an answer is consistent with every hint it resulted
+/// in.
+fn choose_guess<'a>(best_guesses: &[(Vec<usize>, &'a Craft<'a>)], book: Option<&'a Book>, answers: &'a [Answer], tried: &[(&Craft, Hint)], ctx: SolverContext) -> &'a Craft<'a> {
+    if let Some(book) = book.filter(|book| is_allowed(&book.guess, tried, ctx)) {
+        return &book.guess;
+    }
+
+    if let Some((_, guess)) = best_guesses.iter().find(|(_, guess)| is_allowed(guess, tried, ctx)) {
+        return guess;
+    }
+
+    answers.iter()
+        .find(|answer| tried.iter().all(|(prior, hint)| is_consistent(answer, prior, hint)))
+        .map(|answer| &answer.craft)
+        .expect("the answer is consistent with every hint it resulted in")
+}
+
+/// Determines whether a guess can be made after the guesses in `tried`
+/// resulted in their hints. In hard mode, it must be consistent with all of
+/// them.
+fn is_allowed(guess: &Craft, tried: &[(&Craft, Hint)], ctx: SolverContext) -> bool {
+    !ctx.hard_mode || tried.iter().all(|(prior, hint)| is_consistent(&ctx.labels.as_answer(guess), prior, hint))
+}
+
+/// Returns the book to follow after its guess resulted in a hint. In hard
+/// mode, it's only followed while its guess is one of the allowed `guesses`.
+pub fn follow_book<'a>(book: Option<&'a Book>, hint: &Hint, guesses: &[Craft], ctx: SolverContext) -> Option<&'a Book> {
+    book.and_then(|book| book.next(hint))
+        .filter(|next| !ctx.hard_mode || guesses.contains(&next.guess))
+}
+
+/// The guesses that can be made after a guess resulted in a hint. In hard mode,
+/// these are only the guesses consistent with the hint, or the remaining
+/// answers if none are.
+pub fn next_guesses<'a>(guesses: &'a [Craft<'a>], answers: &[Answer], ctx: SolverContext, guess: &Craft, hint: &Hint) -> Cow<'a, [Craft<'a>]> {
+    if !ctx.hard_mode {
+        return Cow::Borrowed(guesses);
+    }
+
+    let consistent = guesses.iter()
+        .filter(|next| is_consistent(&ctx.labels.as_answer(next), guess, hint))
+        .copied()
+        .collect_vec();
+
+    if consistent.is_empty() {
+        Cow::Owned(answers.iter().map(|answer| answer.craft).unique().collect_vec())
+    } else {
+        Cow::Owned(consistent)
+    }
+}
+
+/// Determines whether a guess is allowed in hard mode after an earlier guess
+/// resulted in a hint: the guess, treated as the answer, must result in the
+/// same hint.
+pub fn is_consistent(next: &Answer, guess: &Craft, hint: &Hint) -> bool {
+    calculate_hint(next, guess) == *hint
 }
 
 /// Simulates a greedy algorithm against an adversarial game.
-/// This is pretty much the simplest case imaginable.
-fn greedy_adversarial(answers: &Vec<Craft>, guesses: &Vec<Craft>) -> usize {
-    let min = guesses.iter()
-    .map(|guess| assemble_pools(&guess, &answers))
-    .map(|pools| pools.values().max_by_key(|v| v.len()).unwrap().clone())
-    .zip(guesses)
-    .min_set_by_key(|(a, _)| a.len());
+/// This is pretty much the simplest case imaginable. Returns every guess, with
+/// the number of possible answers before and after it.
+fn greedy_adversarial(answers: &[Answer], guesses: &[Craft], ctx: SolverContext) -> Vec<(usize, usize, Craft<'static>)> {
+    let guess = most_information(answers, guesses, ctx).first().unwrap().1;
 
-    let min = min.get(0).unwrap();
+    // The adversary always picks the largest pool
+    let (hint, pool) = assemble_pools(guess, answers, ctx.matrix).into_iter().max_by_key(|(_, pool)| pool.len()).unwrap();
+    let guesses = next_guesses(guesses, &pool, ctx, guess, &hint.unpack());
 
-    println!("From {:?} to {:?} possible solutions by {:?}", answers.len(), min.0.len(), fmt(min.1));
+    let mut steps = vec![(answers.len(), pool.len(), *guess)];
 
-    if min.0.len() == 1 {
-        if &min.0[0] == min.1 {
-            1
-        } else {
+    if pool.len() == 1 {
+        if !pool[0].accepts(guess) {
             // Simulate another guess
-            1 + greedy_adversarial(&min.0, &min.0)
+            let guesses = pool.iter().map(|answer| answer.craft).collect_vec();
+            steps.extend(greedy_adversarial(&pool, &guesses, ctx));
         }
     } else {
-        1 + greedy_adversarial(&min.0, guesses)
+        steps.extend(greedy_adversarial(&pool, &guesses, ctx));
     }
+
+    steps
 }
 
-fn assemble_pools<'a>(guess: &Craft, answers: &'a Vec<Craft>) -> HashMap<Hint, Vec<Craft<'a>>> {
+fn assemble_pools(guess: &Craft, answers: &[Answer], matrix: &HintMatrix) -> HashMap<PackedHint, Vec<Answer>> {
     let mut map: HashMap<_, Vec<_>> = HashMap::new();
 
-    for answer in answers.clone() {
-        let overlap = calculate_hint(&answer, guess);
+    let row = matrix.row(guess);
+
+    for answer in answers.iter().copied() {
+        let overlap = match row {
+            Some(row) => matrix.get(row, &answer),
+            None => PackedHint::pack(&calculate_hint(&answer, guess)),
+        };
         
         if let Some(vec) = map.get_mut(&overlap) {
             vec.push(answer);
@@ -365,11 +959,60 @@ fn assemble_pools<'a>(guess: &Craft, answers: &'a Vec<Craft>) -> HashMap<Hint, V
     map
 }
 
-fn calculate_hint(answer: &Craft, guess: &Craft) -> Hint {
-    if answer == guess {
+fn calculate_hint(answer: &Answer, guess: &Craft) -> Hint {
+    if answer.accepts(guess) {
         return [Color::Green; 9];
     }
 
+    match answer.placement {
+        Placement::Exact => calculate_grid_hint(&answer.craft, guess),
+        Placement::Shaped => calculate_shaped_hint(&answer.craft, guess),
+        Placement::Shapeless => calculate_shapeless_hint(&answer.craft, guess),
+    }
+}
+
+/// Calculates the hint for a shaped answer, which may be placed at any offset
+/// and mirrored. The hint is calculated against the placement that matches the
+/// guess best, preferring more greens and then more yellows.
+fn calculate_shaped_hint(answer: &Craft, guess: &Craft) -> Hint {
+    let score = |hint: &Hint| (
+        hint.iter().filter(|c| **c == Color::Green).count(),
+        hint.iter().filter(|c| **c == Color::Yellow).count(),
+    );
+
+    let mut best = calculate_grid_hint(answer, guess);
+
+    for placement in placements(answer) {
+        let hint = calculate_grid_hint(&placement, guess);
+
+        if score(&hint) > score(&best) {
+            best = hint;
+        }
+    }
+
+    best
+}
+
+/// Calculates the hint for a shapeless answer, which may be placed anywhere.
+/// Since the answer can always be arranged to match the guess, every material
+/// that's in the answer is green, up to the number of times it appears.
+fn calculate_shapeless_hint(answer: &Craft, guess: &Craft) -> Hint {
+    let mut hint = [Color::Gray; 9];
+
+    let mut remaining = answer.iter().filter(|m| **m != Material::default()).counts();
+
+    for (index, material) in guess.iter().enumerate() {
+        if let Some(count) = remaining.get_mut(material).filter(|count| **count > 0) {
+            hint[index] = Color::Green;
+            *count -= 1;
+        }
+    }
+
+    hint
+}
+
+/// Calculates the hint for an answer placed as exactly the provided grid.
+fn calculate_grid_hint(answer: &Craft, guess: &Craft) -> Hint {
     let mut hint = [Color::Gray; 9];
 
     let mut used = [false; 9];
@@ -396,3 +1039,162 @@ fn calculate_hint(answer: &Craft, guess: &Craft) -> Hint {
 
     hint
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub const AIR: Material = Material(0);
+    pub const PLANKS: Material = Material(1);
+    pub const COBBLESTONE: Material = Material(2);
+    pub const STICK: Material = Material(6);
+
+    use Color::{Gray as X, Green as G, Yellow as Y};
+
+    /// An answer placed as the provided grid.
+    fn answer(craft: Craft<'static>, placement: Placement) -> Answer {
+        Answer { index: 0, origin: Origin::default(), craft, placement }
+    }
+
+    /// The guesses and answers generated from a few recipes, like `run` does.
+    pub struct Fixture {
+        pub palette: Palette,
+        pub guesses: Vec<Craft<'static>>,
+        pub answers: Vec<Answer>,
+        pub labels: Labels,
+        pub matrix: HintMatrix,
+    }
+
+    impl Fixture {
+        pub fn new(recipes: Vec<(&str, Recipe)>, model: HintModel) -> Self {
+            let recipes = recipes.into_iter().map(|(name, recipe)| (name.to_owned(), recipe)).collect_vec();
+
+            let labelled = recipes.iter().enumerate()
+                .flat_map(|(recipe, r)| permutations_guess(&r.1).into_iter().enumerate()
+                    .map(move |(variant, craft)| (Origin { recipe, variant }, craft)))
+                .collect_vec();
+
+            let guesses = labelled.iter().map(|(_, craft)| *craft).collect_vec();
+
+            let answers = recipes.iter().enumerate()
+                .flat_map(|(recipe, r)| permutations_answer(&r.1, get_shaped_offset, get_shapeless_slots, false).into_iter()
+                    .map(move |answer| Answer { origin: Origin { recipe, ..answer.origin }, ..answer }))
+                .enumerate()
+                .map(|(index, answer)| model.apply(Answer { index, ..answer }))
+                .collect_vec();
+
+            let labels = Labels::new(&recipes, &labelled, &answers);
+            let matrix = HintMatrix::new(&answers, &guesses);
+
+            Self { palette: Palette::default(), guesses, answers, labels, matrix }
+        }
+
+        /// A few small recipes, with shaped, mirrored, and shapeless answers.
+        pub fn recipes(model: HintModel) -> Self {
+            Self::new(vec![
+                ("stick", Recipe::Shaped(vec![vec![vec![PLANKS]], vec![vec![PLANKS]]])),
+                ("torch", Recipe::Shaped(vec![vec![vec![STICK]], vec![vec![COBBLESTONE]]])),
+                ("axe", Recipe::Shaped(vec![
+                    vec![vec![PLANKS], vec![PLANKS]],
+                    vec![vec![PLANKS], vec![STICK]],
+                    vec![vec![Material::default()], vec![STICK]],
+                ])),
+                ("button", Recipe::Shapeless(vec![vec![PLANKS]])),
+                ("pressure_plate", Recipe::Shapeless(vec![vec![COBBLESTONE], vec![PLANKS, STICK]])),
+            ], model)
+        }
+
+        pub fn ctx(&self, hard_mode: bool) -> SolverContext<'_> {
+            SolverContext { palette: &self.palette, scorer: ScorerKind::Minimax.scorer(), matrix: &self.matrix, labels: &self.labels, hard_mode }
+        }
+    }
+
+    #[test]
+    fn answers_are_consistent_with_their_own_hints() {
+        for model in [HintModel::Fixed, HintModel::AnyPlacement] {
+            let fixture = Fixture::recipes(model);
+
+            let mut guesses = fixture.guesses.clone();
+            guesses.push([PLANKS, PLANKS, PLANKS, Material::default(), Material::default(), Material::default(), Material::default(), Material::default(), Material::default()]);
+
+            for answer in &fixture.answers {
+                for guess in &guesses {
+                    let hint = calculate_hint(answer, guess);
+
+                    assert!(is_consistent(&fixture.labels.as_answer(&answer.craft), guess, &hint), "{answer:?} with {guess:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chooses_a_possible_answer_when_no_best_guess_is_allowed() {
+        let fixture = Fixture::recipes(HintModel::AnyPlacement);
+        let ctx = fixture.ctx(true);
+
+        let answer = fixture.answers.iter().find(|answer| answer.placement == Placement::Shapeless).unwrap();
+        let (prior, other) = fixture.guesses.iter()
+            .filter(|guess| !answer.accepts(guess))
+            .tuple_combinations()
+            .find(|(prior, other)| !is_consistent(&fixture.labels.as_answer(other), prior, &calculate_hint(answer, prior)))
+            .unwrap();
+
+        let tried = [(prior, calculate_hint(answer, prior))];
+        let best_guesses = [(vec![1], other)];
+
+        assert_eq!(choose_guess(&best_guesses, None, &[*answer], &tried, ctx), &answer.craft);
+        assert_eq!(choose_guess(&best_guesses, None, &[*answer], &tried, fixture.ctx(false)), other);
+    }
+
+    #[test]
+    fn shapeless_answers_accept_any_arrangement() {
+        let answer = answer([COBBLESTONE, STICK, AIR, AIR, AIR, AIR, AIR, AIR, AIR], Placement::Shapeless);
+
+        assert!(answer.accepts(&[AIR, AIR, AIR, AIR, STICK, AIR, AIR, AIR, COBBLESTONE]));
+        assert!(!answer.accepts(&[AIR, AIR, AIR, AIR, STICK, AIR, AIR, AIR, AIR]));
+        assert!(!answer.accepts(&[COBBLESTONE, STICK, STICK, AIR, AIR, AIR, AIR, AIR, AIR]));
+
+        assert_eq!(calculate_hint(&answer, &[AIR, AIR, COBBLESTONE, AIR, AIR, AIR, STICK, AIR, AIR]), [G; 9]);
+    }
+
+    #[test]
+    fn shapeless_hints_are_green_up_to_the_count_of_each_material() {
+        let answer = answer([PLANKS, PLANKS, STICK, AIR, AIR, AIR, AIR, AIR, AIR], Placement::Shapeless);
+
+        assert_eq!(
+            calculate_hint(&answer, &[STICK, COBBLESTONE, PLANKS, PLANKS, PLANKS, AIR, AIR, AIR, STICK]),
+            [G, X, G, G, X, X, X, X, X],
+        );
+    }
+
+    #[test]
+    fn shaped_answers_accept_any_offset_and_mirror() {
+        let craft = [PLANKS, PLANKS, AIR, STICK, AIR, AIR, AIR, AIR, AIR];
+        let shaped = answer(craft, Placement::Shaped);
+        let exact = HintModel::Fixed.apply(shaped);
+
+        let moved = [AIR, AIR, AIR, AIR, PLANKS, PLANKS, AIR, STICK, AIR];
+        let mirrored = [AIR, PLANKS, PLANKS, AIR, AIR, STICK, AIR, AIR, AIR];
+        let transposed = [PLANKS, STICK, AIR, PLANKS, AIR, AIR, AIR, AIR, AIR];
+
+        assert!(shaped.accepts(&craft) && shaped.accepts(&moved) && shaped.accepts(&mirrored));
+        assert!(!shaped.accepts(&transposed));
+
+        assert!(exact.accepts(&craft));
+        assert!(!exact.accepts(&moved) && !exact.accepts(&mirrored));
+    }
+
+    #[test]
+    fn shaped_hints_use_the_best_placement() {
+        let stick = [AIR, PLANKS, AIR, AIR, PLANKS, AIR, AIR, AIR, AIR];
+        let guess = [AIR, AIR, AIR, AIR, AIR, PLANKS, AIR, AIR, COBBLESTONE];
+
+        assert_eq!(calculate_hint(&answer(stick, Placement::Shaped), &guess), [X, X, X, X, X, G, X, X, X]);
+        assert_eq!(calculate_hint(&answer(stick, Placement::Exact), &guess), [X, X, X, X, X, Y, X, X, X]);
+
+        // No other placement matches better, so the answer's own is kept
+        let planks = [PLANKS, PLANKS, PLANKS, AIR, AIR, AIR, AIR, AIR, AIR];
+
+        assert_eq!(calculate_hint(&answer(stick, Placement::Shaped), &planks), [Y, G, Y, X, X, X, X, X, X]);
+    }
+}
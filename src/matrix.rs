@@ -0,0 +1,204 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{Context, Result};
+use itertools::Itertools;
+
+use crate::{calculate_hint, Answer, Color, Craft, Hint};
+
+/// The magic bytes at the start of a cached hint matrix.
+const MAGIC: &[u8; 4] = b"MCHM";
+
+/// The version of the cached hint matrix format.
+const VERSION: u32 = 1;
+
+/// A hint packed into a base 3 number, with the first slot as the least
+/// significant digit. There are 3^9 = 19683 possible hints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackedHint(pub u16);
+
+impl PackedHint {
+    /// The hint for a correct guess.
+    pub const SOLVED: PackedHint = PackedHint(19682);
+
+    /// The number of possible hints.
+    pub const COUNT: usize = 19683;
+
+    pub fn pack(hint: &Hint) -> Self {
+        PackedHint(hint.iter().rev().fold(0, |packed, color| packed * 3 + *color as u16))
+    }
+
+    pub fn unpack(self) -> Hint {
+        let mut hint = [Color::Gray; 9];
+        let mut packed = self.0;
+
+        for color in &mut hint {
+            *color = match packed % 3 {
+                0 => Color::Gray,
+                1 => Color::Yellow,
+                _ => Color::Green,
+            };
+            packed /= 3;
+        }
+
+        hint
+    }
+}
+
+/// A precomputed table of the hint for every guess against every answer.
+pub struct HintMatrix {
+    answers: usize,
+    hints: Vec<PackedHint>,
+    rows: HashMap<Craft<'static>, usize>,
+}
+
+impl HintMatrix {
+    /// Calculates the hint for every guess against every answer.
+    /// The answers must be indexed by their position in the slice.
+    pub fn new(answers: &[Answer], guesses: &[Craft]) -> Self {
+        let hints = guesses.iter()
+            .flat_map(|guess| answers.iter().map(|answer| PackedHint::pack(&calculate_hint(answer, guess))))
+            .collect_vec();
+
+        Self::from_hints(answers, guesses, hints)
+    }
+
+    fn from_hints(answers: &[Answer], guesses: &[Craft], hints: Vec<PackedHint>) -> Self {
+        let mut rows = HashMap::new();
+        for (row, guess) in guesses.iter().enumerate() {
+            rows.entry(*guess).or_insert(row);
+        }
+
+        Self { answers: answers.len(), hints, rows }
+    }
+
+    /// Reads the matrix from the cache file at the provided path if it was
+    /// calculated for the same answers and guesses, and otherwise calculates
+    /// it and writes it to the cache file.
+    pub fn cached(path: &Path, answers: &[Answer], guesses: &[Craft]) -> Result<Self> {
+        let fingerprint = fingerprint(answers, guesses);
+
+        if let Ok(bytes) = fs::read(path) {
+            match Self::decode(&bytes, fingerprint, answers, guesses) {
+                Some(matrix) => return Ok(matrix),
+                None => eprintln!("Hint cache {path:?} is outdated; recalculating"),
+            }
+        }
+
+        let matrix = Self::new(answers, guesses);
+
+        let mut bytes = Vec::with_capacity(24 + matrix.hints.len() * 2);
+        bytes.extend(MAGIC);
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend(fingerprint.to_le_bytes());
+        bytes.extend((guesses.len() as u32).to_le_bytes());
+        bytes.extend((answers.len() as u32).to_le_bytes());
+        bytes.extend(matrix.hints.iter().flat_map(|hint| hint.0.to_le_bytes()));
+
+        fs::write(path, bytes)
+            .with_context(|| format!("while writing hint cache {path:?}"))?;
+
+        Ok(matrix)
+    }
+
+    fn decode(bytes: &[u8], fingerprint: u64, answers: &[Answer], guesses: &[Craft]) -> Option<Self> {
+        let (header, body) = bytes.split_at_checked(24)?;
+
+        let u32_at = |index: usize| u32::from_le_bytes(header[index..index + 4].try_into().unwrap());
+
+        let valid = &header[0..4] == MAGIC
+            && u32_at(4) == VERSION
+            && u64::from_le_bytes(header[8..16].try_into().unwrap()) == fingerprint
+            && u32_at(16) as usize == guesses.len()
+            && u32_at(20) as usize == answers.len()
+            && body.len() == guesses.len() * answers.len() * 2;
+
+        if !valid {
+            return None;
+        }
+
+        let hints = body.chunks_exact(2)
+            .map(|chunk| PackedHint(u16::from_le_bytes([chunk[0], chunk[1]])))
+            .collect_vec();
+
+        Some(Self::from_hints(answers, guesses, hints))
+    }
+
+    /// Returns the row of a guess, if it's in the matrix.
+    pub fn row(&self, guess: &Craft) -> Option<usize> {
+        self.rows.get(guess).copied()
+    }
+
+    /// Returns the hint for the guess in the provided row against an answer.
+    pub fn get(&self, row: usize, answer: &Answer) -> PackedHint {
+        self.hints[row * self.answers + answer.index]
+    }
+
+    /// Returns the hint for a guess against an answer, looking it up if
+    /// possible and calculating it otherwise.
+    pub fn hint(&self, guess: &Craft, answer: &Answer) -> PackedHint {
+        match self.row(guess) {
+            Some(row) => self.get(row, answer),
+            None => PackedHint::pack(&calculate_hint(answer, guess)),
+        }
+    }
+}
+
+/// Calculates a stable hash of the answers and guesses, which changes if any
+/// of them do. This uses 64-bit FNV-1a so that it's the same across builds.
+pub fn fingerprint(answers: &[Answer], guesses: &[Craft]) -> u64 {
+    let bytes = answers.iter()
+        .flat_map(|answer| answer.craft.iter().map(|m| m.0).chain([answer.placement as u8]))
+        .chain([0xFF])
+        .chain(guesses.iter().flat_map(|guess| guess.iter().map(|m| m.0)));
+
+    hash_bytes(0xcbf29ce484222325, bytes)
+}
+
+/// Continues a 64-bit FNV-1a hash with more bytes.
+pub fn hash_bytes(hash: u64, bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(hash, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::Fixture, HintModel};
+
+    use super::*;
+
+    #[test]
+    fn packs_every_hint_reversibly() {
+        for packed in 0..PackedHint::COUNT as u16 {
+            assert_eq!(PackedHint::pack(&PackedHint(packed).unpack()), PackedHint(packed));
+        }
+
+        assert_eq!(PackedHint::SOLVED.unpack(), [Color::Green; 9]);
+    }
+
+    #[test]
+    fn reads_caches_of_the_same_answers_and_guesses() {
+        let fixture = Fixture::recipes(HintModel::AnyPlacement);
+        let path = std::env::temp_dir().join(format!("solving-minecraftle-hints-{}", std::process::id()));
+
+        let written = HintMatrix::cached(&path, &fixture.answers, &fixture.guesses).unwrap();
+
+        // Change the first hint, so that reading the cache can be told apart
+        // from recalculating it
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[24] ^= 1;
+        fs::write(&path, &bytes).unwrap();
+
+        let read = HintMatrix::cached(&path, &fixture.answers, &fixture.guesses).unwrap();
+
+        // A different fingerprint means different recipes or options
+        bytes[8] ^= 1;
+        fs::write(&path, &bytes).unwrap();
+
+        let recalculated = HintMatrix::cached(&path, &fixture.answers, &fixture.guesses).unwrap();
+
+        fs::remove_file(&path).unwrap();
+
+        assert_ne!(read.hints[0], written.hints[0]);
+        assert_eq!(read.hints[1..], written.hints[1..]);
+        assert_eq!(recalculated.hints, written.hints);
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+
+use crate::{palette::Palette, Color, Craft, Hint, Material};
+
+/// Writes a craft in grid notation: the short code of each material, with
+/// rows separated by `/`, e.g. `PPP/CiC/CrC`. If any code in the palette is
+/// longer than one character, codes within a row are separated by spaces.
+pub fn to_notation(craft: &Craft, palette: &Palette) -> String {
+    let separator = if single_character_codes(palette) { "" } else { " " };
+
+    craft.chunks(3)
+        .map(|row| row.iter().map(|m| &palette.info(*m).code).join(separator))
+        .join("/")
+}
+
+/// Reads a craft in grid notation. Each row may be written as its codes
+/// without separators, or as three space-separated materials, each of which
+/// may also be an item ID or name.
+pub fn parse_notation(text: &str, palette: &Palette) -> Result<Craft<'static>> {
+    let rows = text.split('/').map(str::trim).collect_vec();
+
+    if rows.len() != 3 {
+        return Err(anyhow!("expected 3 rows separated by `/` in grid {text:?}, found {}", rows.len()));
+    }
+
+    let mut craft = [Material::default(); 9];
+
+    for (y, row) in rows.into_iter().enumerate() {
+        let materials = match row.split_whitespace().collect_vec()[..] {
+            [codes] => split_codes(codes, 3, palette).ok_or_else(|| anyhow!("can't split row {codes:?} into 3 material codes"))?,
+            ref words => words.iter()
+                .map(|word| palette.find(word).ok_or_else(|| anyhow!("unknown material {word:?}")))
+                .collect::<Result<Vec<_>>>()?,
+        };
+
+        if materials.len() != 3 {
+            return Err(anyhow!("expected 3 materials in row {row:?}, found {}", materials.len()));
+        }
+
+        craft[y * 3..y * 3 + 3].copy_from_slice(&materials);
+    }
+
+    Ok(craft)
+}
+
+/// Splits a row of codes without separators into the provided number of
+/// materials, trying longer codes first and backtracking when the rest of the
+/// row can't be split.
+fn split_codes(codes: &str, count: usize, palette: &Palette) -> Option<Vec<Material>> {
+    if codes.is_empty() || count == 0 {
+        return (codes.is_empty() && count == 0).then(Vec::new);
+    }
+
+    all_materials(palette)
+        .filter(|material| codes.starts_with(palette.info(*material).code.as_str()))
+        .sorted_by_key(|material| std::cmp::Reverse(palette.info(*material).code.len()))
+        .find_map(|material| {
+            let mut rest = split_codes(&codes[palette.info(material).code.len()..], count - 1, palette)?;
+            rest.insert(0, material);
+            Some(rest)
+        })
+}
+
+/// Every material in the palette, including air.
+fn all_materials(palette: &Palette) -> impl Iterator<Item = Material> {
+    (0..palette.len()).map(|index| Material(index as u8))
+}
+
+fn single_character_codes(palette: &Palette) -> bool {
+    all_materials(palette).all(|material| palette.info(material).code.chars().count() == 1)
+}
+
+/// Renders a craft as a 3x3 grid of material codes over multiple lines. If a
+/// hint is provided, each slot is colored by it with ANSI escape codes, unless
+/// the `NO_COLOR` environment variable is set.
+pub fn render(craft: &Craft, hint: Option<&Hint>, palette: &Palette) -> String {
+    let width = all_materials(palette)
+        .map(|material| palette.info(material).code.chars().count())
+        .max()
+        .unwrap_or(1);
+
+    let color = std::env::var_os("NO_COLOR").is_none();
+
+    craft.chunks(3).enumerate()
+        .map(|(y, row)| row.iter().enumerate()
+            .map(|(x, material)| {
+                let cell = format!(" {:^width$} ", palette.info(*material).code);
+
+                match hint {
+                    Some(hint) if color => format!("{}{cell}\x1b[0m", ansi(hint[x + y * 3])),
+                    Some(hint) => format!("{}{}", cell.trim_end(), symbol(hint[x + y * 3])),
+                    None => cell,
+                }
+            })
+            .join(""))
+        .join("\n")
+}
+
+/// The ANSI escape code that colors a slot like the game does.
+fn ansi(color: Color) -> &'static str {
+    match color {
+        Color::Green => "\x1b[30;42m",
+        Color::Yellow => "\x1b[30;43m",
+        Color::Gray => "\x1b[97;100m",
+    }
+}
+
+/// The symbol that marks a slot's color when colors are disabled.
+fn symbol(color: Color) -> char {
+    match color {
+        Color::Green => 'G',
+        Color::Yellow => 'Y',
+        Color::Gray => '.',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn palette(codes: &[&str]) -> Palette {
+        let materials = codes.iter()
+            .map(|code| json!({ "id": format!("test:{code}"), "name": code, "code": code }))
+            .collect_vec();
+
+        Palette::parse(&json!({ "materials": materials })).unwrap()
+    }
+
+    #[test]
+    fn round_trips_the_default_palette() {
+        let palette = Palette::default();
+
+        let craft = parse_notation("PPP/CiC/CrC", &palette).unwrap();
+
+        assert_eq!(to_notation(&craft, &palette), "PPP/CiC/CrC");
+    }
+
+    #[test]
+    fn splits_codes_that_share_prefixes() {
+        let palette = palette(&["ab", "a", "bc"]);
+        let [ab, a, bc] = ["ab", "a", "bc"].map(|code| palette.find(code).unwrap());
+
+        // Taking the longest code first would leave `c.`, which isn't a code
+        assert_eq!(split_codes("abc.", 3, &palette), Some(vec![a, bc, Material::default()]));
+        assert_eq!(split_codes("aba", 3, &palette), None);
+        assert_eq!(split_codes("abaab", 3, &palette), Some(vec![ab, a, ab]));
+
+        let craft = [a, bc, ab, bc, a, a, Material::default(), ab, bc];
+        assert_eq!(parse_notation(&to_notation(&craft, &palette), &palette).unwrap(), craft);
+        assert_eq!(parse_notation("abc./bcaa/..ab", &palette).unwrap()[..3], [a, bc, Material::default()]);
+    }
+}
@@ -0,0 +1,158 @@
+use std::{collections::HashMap, ops::Range, path::Path, sync::atomic::{AtomicUsize, Ordering}};
+
+use anyhow::{anyhow, Context, Result};
+use itertools::Itertools;
+use rayon::prelude::*;
+use serde_json::{json, Value};
+
+use crate::{checkpoint::{config_hash, Checkpoint, Timer}, matrix::{HintMatrix, PackedHint}, Answer, Craft};
+
+/// The best openers found by a search, as rows of the hint matrix.
+#[derive(Debug)]
+pub struct Openers {
+    /// The size of the largest pool of answers left after the openers.
+    pub max_pool: usize,
+    /// Every set of openers that leaves a largest pool of `max_pool`.
+    pub openers: Vec<Vec<usize>>,
+}
+
+impl Openers {
+    /// No openers, which any other result replaces.
+    fn none() -> Openers {
+        Openers { max_pool: usize::MAX, openers: Vec::new() }
+    }
+
+    /// Serializes the openers to JSON, with the openers as rows.
+    fn to_json(&self) -> Value {
+        json!({
+            "max_pool": (!self.openers.is_empty()).then_some(self.max_pool),
+            "openers": self.openers,
+        })
+    }
+
+    fn parse(json: &Value) -> Result<Openers> {
+        let Some(openers) = json.get("openers").and_then(Value::as_array) else {
+            return Err(anyhow!("expected array at path 'openers'"));
+        };
+
+        let openers = openers.iter()
+            .map(|rows| rows.as_array()
+                .and_then(|rows| rows.iter().map(|row| row.as_u64().map(|row| row as usize)).collect::<Option<Vec<_>>>())
+                .ok_or_else(|| anyhow!("expected array of integers at path 'openers'")))
+            .collect::<Result<Vec<_>>>()?;
+
+        let max_pool = match json.get("max_pool").and_then(Value::as_u64) {
+            Some(max_pool) => max_pool as usize,
+            None if openers.is_empty() => usize::MAX,
+            None => return Err(anyhow!("expected integer at path 'max_pool'")),
+        };
+
+        Ok(Openers { max_pool, openers })
+    }
+
+    /// Merges two results, keeping only the best openers of both.
+    fn merge(mut self, mut other: Openers) -> Openers {
+        match self.max_pool.cmp(&other.max_pool) {
+            std::cmp::Ordering::Less => self,
+            std::cmp::Ordering::Greater => other,
+            std::cmp::Ordering::Equal => {
+                self.openers.append(&mut other.openers);
+                self
+            },
+        }
+    }
+}
+
+/// Finds every set of `k` guesses that, when guessed together at the start of
+/// the game, leaves the smallest worst-case pool of remaining answers.
+///
+/// If a checkpoint path is provided, progress is periodically written to it,
+/// and the search resumes from it if it already exists.
+pub fn best_openers(answers: &[Answer], guesses: &[Craft], matrix: &HintMatrix, k: usize, checkpoint: Option<&Path>) -> Result<Openers> {
+    let config = config_hash(answers, guesses, "openers", &[k as u64]);
+
+    let (mut next, mut found) = match checkpoint.map(|path| Checkpoint::load(path, config)).transpose()?.flatten() {
+        Some(checkpoint) => {
+            eprintln!("Resuming from checkpoint at guess {} of {}", checkpoint.next, guesses.len());
+
+            (checkpoint.next, Openers::parse(&checkpoint.best).context("while parsing checkpoint")?)
+        },
+        None => (0, Openers::none()),
+    };
+
+    let best = AtomicUsize::new(found.max_pool);
+
+    // Without a checkpoint, everything is searched at once so that threads
+    // aren't left idle waiting for the slowest part of a chunk.
+    let chunk = match checkpoint {
+        Some(_) => rayon::current_num_threads() * 4,
+        None => guesses.len().max(1),
+    };
+
+    let mut timer = Timer::start();
+
+    while next < guesses.len() {
+        let end = (next + chunk).min(guesses.len());
+
+        found = found.merge(search(answers, guesses.len(), matrix, k, next..end, &best));
+        next = end;
+
+        if let Some(path) = checkpoint {
+            if timer.due() || next == guesses.len() {
+                Checkpoint { config, next, best: found.to_json() }.save(path)?;
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// Searches every set of guesses whose first guess is in the range.
+///
+/// Sets are split across threads by their first guess. Every thread shares the
+/// best worst-case pool found so far, and stops counting a set as soon as one
+/// of its pools is already larger.
+fn search(answers: &[Answer], guesses: usize, matrix: &HintMatrix, k: usize, firsts: Range<usize>, best: &AtomicUsize) -> Openers {
+    firsts.into_par_iter()
+        .map_init(HashMap::new, |counts, first| {
+            let mut found = Openers::none();
+
+            for rest in (first + 1..guesses).combinations(k.saturating_sub(1)) {
+                let rows = [first].into_iter().chain(rest).collect_vec();
+
+                let Some(max_pool) = max_pool(answers, matrix, &rows, best.load(Ordering::Relaxed), counts) else {
+                    continue;
+                };
+
+                best.fetch_min(max_pool, Ordering::Relaxed);
+
+                found = found.merge(Openers { max_pool, openers: vec![rows] });
+            }
+
+            found
+        })
+        .reduce(Openers::none, Openers::merge)
+}
+
+/// Calculates the size of the largest pool left after guessing every row,
+/// or `None` if it's larger than the limit.
+fn max_pool(answers: &[Answer], matrix: &HintMatrix, rows: &[usize], limit: usize, counts: &mut HashMap<u64, usize>) -> Option<usize> {
+    counts.clear();
+
+    let mut max = 0;
+
+    for answer in answers {
+        let key = rows.iter().fold(0, |key, row| key * PackedHint::COUNT as u64 + matrix.get(*row, answer).0 as u64);
+
+        let count = counts.entry(key).or_insert(0);
+        *count += 1;
+
+        if *count > limit {
+            return None;
+        }
+
+        max = max.max(*count);
+    }
+
+    Some(max)
+}
@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+use itertools::Itertools;
+
+use crate::{calculate_hint, matrix::PackedHint, tree::{build_tree, Branch, Node}, Answer, Color, SolverContext, Craft};
+
+/// What an optimal strategy minimizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Objective {
+    /// The average number of guesses over every answer.
+    Average,
+    /// The largest number of guesses for any answer.
+    WorstCase,
+    /// The number of answers that aren't solved within the guess limit, which
+    /// maximizes the probability of solving a random answer in time.
+    Failures,
+}
+
+/// A subset of the answers, as sorted indices.
+type Set = Vec<usize>;
+
+/// The pools a guess splits a set into, with the pool of answers that the
+/// guess solves (if any) kept separately.
+struct Split {
+    guess: Craft<'static>,
+    solved: Set,
+    pools: Vec<(PackedHint, Set)>,
+}
+
+/// The result of searching a set.
+#[derive(Debug, Clone, Copy)]
+enum Entry {
+    /// The exact cost of the set, and the guess that achieves it.
+    Exact(usize, Craft<'static>),
+    /// The cost of the set is at least this.
+    AtLeast(usize),
+}
+
+/// An exhaustive depth-first search for the strategy that minimizes the
+/// objective. Sets of answers that have already been searched are memoized,
+/// and branches that can't beat the best strategy so far are pruned.
+pub struct Search<'a> {
+    answers: &'a [Answer],
+    guesses: &'a [Craft<'a>],
+    ctx: SolverContext<'a>,
+    objective: Objective,
+    width: Option<usize>,
+    /// The number of guesses allowed for [Objective::Failures].
+    limit: usize,
+    /// Whether any guess solves more than one answer at once, which weakens
+    /// the lower bounds used for pruning.
+    shared_guesses: bool,
+    memo: HashMap<Set, Entry>,
+    /// The searched sets for [Objective::Failures], by the number of guesses
+    /// that are left.
+    limited: HashMap<(Set, usize), Entry>,
+}
+
+impl<'a> Search<'a> {
+    /// Creates a search over the answers. Candidate guesses are tried in the
+    /// order of the scorer, and only the best `width` of them are tried at each
+    /// step if it's provided, in which case the result is no longer proven.
+    /// `limit` is the number of guesses allowed, which only matters for
+    /// [Objective::Failures].
+    pub fn new(answers: &'a [Answer], guesses: &'a [Craft], ctx: SolverContext<'a>, objective: Objective, width: Option<usize>, limit: usize) -> Self {
+        let shared_guesses = guesses.iter()
+            .chain(answers.iter().map(|answer| &answer.craft))
+            .any(|guess| answers.iter().filter(|answer| answer.accepts(guess)).count() > 1);
+
+        Self { answers, guesses, ctx, objective, width, limit, shared_guesses, memo: HashMap::new(), limited: HashMap::new() }
+    }
+
+    /// Finds the minimum cost of solving every answer: the total number of
+    /// guesses for [Objective::Average], the number of guesses for the worst
+    /// answer for [Objective::WorstCase], or the number of answers that aren't
+    /// solved within the limit for [Objective::Failures].
+    pub fn solve(&mut self) -> usize {
+        let set = (0..self.answers.len()).collect_vec();
+
+        let cost = match self.objective {
+            Objective::Failures => self.search_limited(&set, self.limit, usize::MAX),
+            _ => self.search(&set, usize::MAX),
+        };
+
+        cost.expect("an unbounded search always finishes")
+    }
+
+    /// Builds the decision tree of the optimal strategy. [Search::solve] must
+    /// have been called first.
+    pub fn tree(&self) -> Node {
+        let set = (0..self.answers.len()).collect_vec();
+
+        match self.objective {
+            Objective::Failures => self.node_limited(&set, self.limit),
+            _ => self.node(&set),
+        }
+    }
+
+    fn node(&self, set: &Set) -> Node {
+        let Some(Entry::Exact(_, guess)) = self.memo.get(set) else {
+            panic!("set was not solved exactly");
+        };
+
+        let split = self.split(set, *guess);
+
+        let mut branches = split.pools.iter()
+            .map(|(hint, pool)| Branch { hint: hint.unpack(), answers: pool.len(), next: Some(self.node(pool)) })
+            .collect_vec();
+
+        if !split.solved.is_empty() {
+            branches.insert(0, Branch { hint: [Color::Green; 9], answers: split.solved.len(), next: None });
+        }
+
+        Node { guess: *guess, answers: set.len(), branches }
+    }
+
+    /// Like [Search::node], but for [Objective::Failures]. Answers that can't
+    /// be solved within the limit are continued with the greedy algorithm.
+    fn node_limited(&self, set: &Set, left: usize) -> Node {
+        let Some(Entry::Exact(_, guess)) = self.limited.get(&(set.clone(), left)) else {
+            let answers = set.iter().map(|index| self.answers[*index]).collect_vec();
+            return build_tree(&answers, self.guesses, self.ctx, None);
+        };
+
+        let split = self.split(set, *guess);
+
+        let mut branches = split.pools.iter()
+            .map(|(hint, pool)| Branch { hint: hint.unpack(), answers: pool.len(), next: Some(self.node_limited(pool, left - 1)) })
+            .collect_vec();
+
+        if !split.solved.is_empty() {
+            branches.insert(0, Branch { hint: [Color::Green; 9], answers: split.solved.len(), next: None });
+        }
+
+        Node { guess: *guess, answers: set.len(), branches }
+    }
+
+    /// The smallest possible cost of a set, without searching it.
+    fn lower_bound(&self, set: &Set) -> usize {
+        let solvable_at_once = if self.shared_guesses { set.len() } else { 1 };
+
+        match self.objective {
+            // Every answer takes a guess, and every answer that isn't solved
+            // by the first guess takes another.
+            Objective::Average => set.len() + (set.len() - solvable_at_once.min(set.len())),
+            Objective::WorstCase => if set.len() > solvable_at_once { 2 } else { 1 },
+            Objective::Failures => 0,
+        }
+    }
+
+    /// Searches for the number of answers in a set that can't be solved with
+    /// the guesses that are left, returning it only if it's less than the
+    /// budget.
+    fn search_limited(&mut self, set: &Set, left: usize, budget: usize) -> Option<usize> {
+        let key = (set.clone(), left);
+
+        match self.limited.get(&key) {
+            Some(Entry::Exact(cost, _)) => return (*cost < budget).then_some(*cost),
+            Some(Entry::AtLeast(bound)) if *bound >= budget => return None,
+            _ => {},
+        }
+
+        if left == 0 {
+            return (set.len() < budget).then_some(set.len());
+        }
+
+        if let [answer] = set[..] {
+            self.limited.insert(key, Entry::Exact(0, self.answers[answer].craft));
+            return (0 < budget).then_some(0);
+        }
+
+        // With one guess left, at most one guess's worth of answers is solved
+        let solvable_at_once = if self.shared_guesses { set.len() } else { 1 };
+        if left == 1 && set.len() - solvable_at_once.min(set.len()) >= budget {
+            return None;
+        }
+
+        let mut best = budget;
+        let mut best_guess = None;
+
+        for split in self.candidates(set) {
+            let mut cost = 0;
+
+            for (_, pool) in &split.pools {
+                match self.search_limited(pool, left - 1, best - cost) {
+                    Some(failures) => cost += failures,
+                    None => {
+                        cost = best;
+                        break;
+                    },
+                }
+            }
+
+            if cost < best {
+                best = cost;
+                best_guess = Some(split.guess);
+            }
+
+            if best == 0 {
+                break;
+            }
+        }
+
+        match best_guess {
+            Some(guess) => {
+                self.limited.insert(key, Entry::Exact(best, guess));
+                Some(best)
+            },
+            None => {
+                self.limited.insert(key, Entry::AtLeast(budget));
+                None
+            },
+        }
+    }
+
+    /// Searches for the cost of a set, returning it only if it's less than the
+    /// budget.
+    fn search(&mut self, set: &Set, budget: usize) -> Option<usize> {
+        match self.memo.get(set) {
+            Some(Entry::Exact(cost, _)) => return (*cost < budget).then_some(*cost),
+            Some(Entry::AtLeast(bound)) if *bound >= budget => return None,
+            _ => {},
+        }
+
+        if self.lower_bound(set) >= budget {
+            return None;
+        }
+
+        if let [answer] = set[..] {
+            self.memo.insert(set.clone(), Entry::Exact(1, self.answers[answer].craft));
+            return Some(1);
+        }
+
+        let mut best = budget;
+        let mut best_guess = None;
+
+        for split in self.candidates(set) {
+            if let Some(cost) = self.cost(set, &split, best) {
+                best = cost;
+                best_guess = Some(split.guess);
+            }
+        }
+
+        match best_guess {
+            Some(guess) => {
+                self.memo.insert(set.clone(), Entry::Exact(best, guess));
+                Some(best)
+            },
+            None => {
+                self.memo.insert(set.clone(), Entry::AtLeast(budget));
+                None
+            },
+        }
+    }
+
+    /// Calculates the cost of making a guess, returning it only if it's less
+    /// than the budget.
+    fn cost(&mut self, set: &Set, split: &Split, budget: usize) -> Option<usize> {
+        match self.objective {
+            Objective::Average => {
+                let mut cost = set.len();
+                let mut remaining = split.pools.iter().map(|(_, pool)| self.lower_bound(pool)).sum::<usize>();
+
+                for (_, pool) in &split.pools {
+                    remaining -= self.lower_bound(pool);
+
+                    let limit = budget.checked_sub(cost + remaining)?;
+                    cost += self.search(pool, limit)?;
+                }
+
+                (cost < budget).then_some(cost)
+            },
+            Objective::WorstCase => {
+                let mut depth = 0;
+
+                for (_, pool) in &split.pools {
+                    depth = depth.max(self.search(pool, budget - 1)?);
+                }
+
+                (depth + 1 < budget).then_some(depth + 1)
+            },
+            Objective::Failures => unreachable!("failures are searched by search_limited"),
+        }
+    }
+
+    /// Returns every useful split of a set, best first according to the
+    /// scorer. Guesses that split the set identically are only returned once,
+    /// and guesses that don't split the set at all are skipped.
+    fn candidates(&self, set: &Set) -> Vec<Split> {
+        let splits = self.guesses.iter()
+            .chain(set.iter().map(|index| &self.answers[*index].craft))
+            .map(|guess| self.split(set, *guess))
+            .filter(|split| !split.solved.is_empty() || split.pools.len() > 1)
+            .unique_by(|split| (split.solved.clone(), split.pools.iter().map(|(_, pool)| pool.clone()).sorted().collect_vec()))
+            .sorted_by_cached_key(|split| {
+                let sizes = split.pools.iter()
+                    .map(|(_, pool)| pool.len())
+                    .chain((!split.solved.is_empty()).then_some(split.solved.len()))
+                    .sorted().rev().collect_vec();
+
+                self.ctx.scorer.score(&sizes)
+            });
+
+        match self.width {
+            Some(width) => splits.take(width).collect_vec(),
+            None => splits.collect_vec(),
+        }
+    }
+
+    fn split(&self, set: &Set, guess: Craft<'static>) -> Split {
+        let mut solved = Vec::new();
+        let mut pools: HashMap<PackedHint, Set> = HashMap::new();
+
+        let row = self.ctx.matrix.row(&guess);
+
+        for index in set {
+            let answer = &self.answers[*index];
+
+            let hint = match row {
+                Some(row) => self.ctx.matrix.get(row, answer),
+                None => PackedHint::pack(&calculate_hint(answer, &guess)),
+            };
+
+            if hint == PackedHint::SOLVED {
+                solved.push(*index);
+            } else {
+                pools.entry(hint).or_default().push(*index);
+            }
+        }
+
+        let pools = pools.into_iter()
+            .sorted_by_key(|(hint, pool)| (std::cmp::Reverse(pool.len()), *hint))
+            .collect_vec();
+
+        Split { guess, solved, pools }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{greedy_algorithm_against_answer, tests::Fixture, HintModel};
+
+    use super::*;
+
+    #[test]
+    fn is_no_worse_than_greedy() {
+        let fixture = Fixture::recipes(HintModel::AnyPlacement);
+        let ctx = fixture.ctx(false);
+
+        let greedy = fixture.answers.iter()
+            .map(|answer| greedy_algorithm_against_answer(&fixture.answers, &fixture.guesses, ctx, answer))
+            .collect_vec();
+
+        let mut search = Search::new(&fixture.answers, &fixture.guesses, ctx, Objective::Average, None, 10);
+        let total = search.solve();
+
+        assert!(total <= greedy.iter().sum(), "{total} against {greedy:?}");
+        assert_eq!(search.tree().guess_counts().iter().sum::<usize>(), total);
+
+        let mut search = Search::new(&fixture.answers, &fixture.guesses, ctx, Objective::WorstCase, None, 10);
+
+        assert!(search.solve() <= *greedy.iter().max().unwrap());
+    }
+}
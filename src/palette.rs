@@ -0,0 +1,182 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+
+use crate::{parse::read_json, Craft, Material};
+
+/// The palette used when none is provided, which is the 18 ingredients of
+/// Minecraftle.
+pub const DEFAULT_PALETTE: &str = include_str!("../palette.json");
+
+/// The item ID of air, which is always the first material of a palette.
+pub const AIR_ID: &str = "minecraft:air";
+
+/// Information about a single material in a palette.
+#[derive(Debug, Clone)]
+pub struct MaterialInfo {
+    /// The item ID, e.g. `minecraft:oak_planks`.
+    pub id: String,
+    /// The name that's displayed when printing crafts.
+    pub name: String,
+    /// A short code used to type and print crafts compactly.
+    pub code: String,
+}
+
+/// The set of materials available in the game.
+/// Materials are stored as indices into this list, with air always at index 0.
+#[derive(Debug, Clone)]
+pub struct Palette {
+    materials: Vec<MaterialInfo>,
+    ids: HashMap<String, Material>,
+}
+
+impl Palette {
+    /// Reads a palette from a JSON file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = read_json(path)?;
+
+        Self::parse(&json).with_context(|| format!("while parsing palette from path {path:?}"))
+    }
+
+    /// Parses a palette from JSON, which must contain an array `materials` of
+    /// objects with the keys `id`, `name`, and `code`.
+    /// Air is always included and must not be listed.
+    pub fn parse(json: &Value) -> Result<Self> {
+        let Some(materials) = json.get("materials").and_then(Value::as_array) else {
+            return Err(anyhow!("expected array at path 'materials'"));
+        };
+
+        let air = MaterialInfo {
+            id: AIR_ID.to_owned(),
+            name: "Air".to_owned(),
+            code: ".".to_owned(),
+        };
+
+        let mut palette = Palette { materials: Vec::new(), ids: HashMap::new() };
+        palette.push(air)?;
+
+        for material in materials {
+            let field = |key: &str| material.get(key)
+                .and_then(Value::as_str)
+                .map(str::to_owned)
+                .ok_or_else(|| anyhow!("expected string at path '{key}' in material {material}"));
+
+            let info = MaterialInfo { id: field("id")?, name: field("name")?, code: field("code")? };
+
+            if info.code.is_empty() || info.code.contains(|c: char| c.is_whitespace() || c == '/') {
+                return Err(anyhow!("invalid code {:?} for material {}", info.code, info.id));
+            }
+
+            palette.push(info)?;
+        }
+
+        Ok(palette)
+    }
+
+    fn push(&mut self, info: MaterialInfo) -> Result<()> {
+        let material = u8::try_from(self.materials.len())
+            .map(Material)
+            .map_err(|_| anyhow!("too many materials in palette"))?;
+
+        if self.ids.insert(info.id.clone(), material).is_some() {
+            return Err(anyhow!("duplicate material {}", info.id));
+        }
+
+        if self.materials.iter().any(|other| other.code == info.code) {
+            return Err(anyhow!("duplicate code {:?} for material {}", info.code, info.id));
+        }
+
+        self.materials.push(info);
+        Ok(())
+    }
+
+    /// Converts an item ID to a material, if it's in the palette.
+    pub fn material(&self, id: &str) -> Option<Material> {
+        self.ids.get(id).copied()
+    }
+
+    /// Finds a material by its short code, item ID, or display name, in that
+    /// order. Display names are matched ignoring case.
+    pub fn find(&self, word: &str) -> Option<Material> {
+        let position = |matches: &dyn Fn(&MaterialInfo) -> bool| self.materials.iter().position(matches);
+
+        position(&|info| info.code == word)
+            .or_else(|| position(&|info| info.id == word))
+            .or_else(|| position(&|info| info.name.eq_ignore_ascii_case(word)))
+            .map(|index| Material(index as u8))
+    }
+
+    /// Returns the information about a material.
+    pub fn info(&self, material: Material) -> &MaterialInfo {
+        &self.materials[material.0 as usize]
+    }
+
+    /// Returns the display name of a material.
+    pub fn name(&self, material: Material) -> &str {
+        &self.info(material).name
+    }
+
+    /// Returns the number of materials, including air.
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    /// Returns whether the palette only contains air.
+    pub fn is_empty(&self) -> bool {
+        self.materials.len() <= 1
+    }
+
+    /// Returns every material in the palette, excluding air.
+    pub fn materials(&self) -> impl Iterator<Item = Material> {
+        (1..self.materials.len()).map(|index| Material(index as u8))
+    }
+
+    /// Converts a list of item IDs to a craft, if every item is in the palette.
+    pub fn craft(&self, ids: [&str; 9]) -> Option<Craft<'static>> {
+        let mut craft = [Material::default(); 9];
+
+        for (slot, id) in craft.iter_mut().zip(ids) {
+            *slot = self.material(id)?;
+        }
+
+        Some(craft)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        let json = serde_json::from_str::<Value>(DEFAULT_PALETTE).expect("default palette is valid JSON");
+
+        Self::parse(&json).expect("default palette is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn palette(count: usize) -> Result<Palette> {
+        let materials = (0..count)
+            .map(|index| json!({ "id": format!("test:item_{index}"), "name": format!("Item {index}"), "code": format!("i{index}") }))
+            .collect::<Vec<_>>();
+
+        Palette::parse(&json!({ "materials": materials }))
+    }
+
+    #[test]
+    fn lists_every_material_of_a_full_palette() {
+        let palette = palette(255).unwrap();
+
+        assert_eq!(palette.len(), 256);
+        assert_eq!(palette.materials().count(), 255);
+        assert_eq!(palette.materials().last(), Some(Material(255)));
+    }
+
+    #[test]
+    fn rejects_more_materials_than_fit() {
+        assert!(palette(256).is_err());
+    }
+}
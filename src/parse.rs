@@ -1,23 +1,181 @@
-use std::{collections::HashMap, fs::{read_dir, read_to_string, DirEntry}, io, path::PathBuf, str::FromStr, vec};
+use std::{collections::HashMap, fs::{read_dir, read_to_string, DirEntry}, io, path::{Path, PathBuf}, vec};
 
 use anyhow::{anyhow, Context, Result};
 use itertools::Itertools;
 use serde_json::Value;
 
-use crate::{Ingredient, Material, Recipe, TAG_DIRECTORY};
+use crate::{archive::Archive, palette::Palette, tags::{Tags, DEFAULT_NAMESPACE}, Ingredient, Material, Recipe};
 
-pub fn list_dir(path: &str) -> Result<Vec<PathBuf>> {
+/// The directory, relative to a data root, containing all relevant recipes.
+/// This can be directly grabbed from Minecraft's data folder.
+pub const RECIPE_DIRECTORY: &str = "recipe";
+
+/// The directory, relative to a data root, containing all relevant tags.
+/// This can be directly grabbed from Minecraft's data folder.
+pub const TAG_DIRECTORY: &str = "tags/item";
+
+/// Somewhere recipes and tags can be read from.
+pub trait DataSource {
+    /// Reads every recipe file, alongside a name used to identify it.
+    fn recipes(&self) -> Result<Vec<(String, Value)>>;
+
+    /// Reads the tag file with the provided namespaced ID, or returns `None`
+    /// if there's no such tag.
+    fn tag(&self, id: &str) -> Result<Option<Value>>;
+}
+
+/// Where recipes and tags are loaded from at runtime.
+#[derive(Debug, Clone)]
+pub struct DataConfig {
+    pub recipes: PathBuf,
+    pub tags: PathBuf,
+    /// The directory containing the data root and the namespaces next to it,
+    /// like a data pack's `data` directory, if the data root has a parent.
+    pub namespaces: Option<PathBuf>,
+}
+
+impl DataConfig {
+    /// Creates a configuration for an extracted data root, which contains the
+    /// recipe and tag directories.
+    pub fn from_root(root: &Path) -> Self {
+        Self {
+            recipes: root.join(RECIPE_DIRECTORY),
+            tags: root.join(TAG_DIRECTORY),
+            namespaces: root.canonicalize().ok().and_then(|root| root.parent().map(Path::to_owned)),
+        }
+    }
+}
+
+impl DataSource for DataConfig {
+    fn recipes(&self) -> Result<Vec<(String, Value)>> {
+        let files = list_dir(&self.recipes)
+            .with_context(|| "while listing files in recipe directory")?;
+
+        files.into_iter()
+            .map(|path| match read_json(&path) {
+                Ok(json) => Ok((format!("{path:?}"), json)),
+                Err(err) => Err(err.context(format!("while parsing path {path:?}")))
+            })
+            .collect::<Result<Vec<_>>>()
+    }
+
+    /// Tags in other namespaces than `minecraft` are read from the tag
+    /// directory of the namespace next to the data root, like in a data
+    /// pack's `data` directory.
+    fn tag(&self, id: &str) -> Result<Option<Value>> {
+        let Some((namespace, name)) = id.split_once(':') else {
+            return Err(anyhow!("invalid name: {}", id));
+        };
+
+        let path = if namespace == DEFAULT_NAMESPACE {
+            self.tags.join(format!("{name}.json"))
+        } else {
+            let Some(namespaces) = &self.namespaces else {
+                return Err(anyhow!("can't read tag {id}: the data root has no parent directory to find namespace {namespace} in"));
+            };
+
+            namespaces.join(namespace).join(TAG_DIRECTORY).join(format!("{name}.json"))
+        };
+
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        read_json(&path).map(Some)
+    }
+}
+
+/// Either extracted data directories or an archive to read data from.
+#[derive(Debug, Clone)]
+pub enum Source {
+    Directory(DataConfig),
+    Archive(PathBuf),
+}
+
+impl Source {
+    /// Creates a source for a data root, which is either a directory containing
+    /// the recipe and tag directories or a client jar or data pack zip.
+    pub fn from_root(root: &Path) -> Self {
+        let archive = root.extension().is_some_and(|ext| ext == "jar" || ext == "zip");
+
+        if archive && root.is_file() {
+            Source::Archive(root.to_owned())
+        } else {
+            Source::Directory(DataConfig::from_root(root))
+        }
+    }
+
+    /// Opens the source so that it can be read from.
+    pub fn open(&self) -> Result<Box<dyn DataSource>> {
+        Ok(match self {
+            Source::Directory(config) => Box::new(config.clone()),
+            Source::Archive(path) => Box::new(Archive::open(path)?),
+        })
+    }
+}
+
+/// A named data source, usually for a specific Minecraft version.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub source: Source,
+}
+
+impl Profile {
+    pub fn new(name: &str, source: Source) -> Self {
+        Self { name: name.to_owned(), source }
+    }
+
+    /// Parses a profile from either `NAME`, which is looked up inside the
+    /// provided data root, or `NAME=ROOT`.
+    pub fn parse(spec: &str, data: &Path) -> Self {
+        match spec.split_once('=') {
+            Some((name, root)) => Self::new(name, Source::from_root(Path::new(root))),
+            None => Self::new(spec, Source::from_root(&data.join(spec))),
+        }
+    }
+}
+
+/// Reads and parses every relevant recipe from the provided source, keeping
+/// only the materials in the provided palette.
+pub fn load_recipes(source: &dyn DataSource, palette: &Palette) -> Result<Vec<(String, Recipe)>> {
+    let json = source.recipes()
+        .with_context(|| "while parsing recipe JSON")?;
+    
+    eprintln!("{} total recipes", json.len());
+
+    let mut tags = Tags::new(source);
+
+    let recipes = json.into_iter()
+        .filter(|(_, json)| filter_recipe(json))
+        .map(|(name, json)| parse_recipe(json, &mut tags, palette)
+            .with_context(|| format!("while parsing recipe {name}"))
+        )
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| "while parsing recipes from JSON")?;
+
+    // Normally we would have to filter out recipes here that have ingredients
+    // with 0 materials, but this is not an issue as the iterated Cartesian
+    // product of them will result in a 0-length list anyway. The `diagnose`
+    // command lists the recipes that this drops.
+    
+    eprintln!("{} relevant recipes (shaped or shapeless)", recipes.len());
+
+    Ok(recipes)
+}
+
+pub fn list_dir(path: &Path) -> Result<Vec<PathBuf>> {
     let dir = read_dir(path)
-        .with_context(|| format!("while trying to list directory {path}"))?;
+        .with_context(|| format!("while trying to list directory {path:?}"))?;
 
     let names = dir.into_iter().collect::<io::Result<Vec<DirEntry>>>()
-        .with_context(|| format!("while trying to parse item in directory list {path}"))?;
+        .with_context(|| format!("while trying to parse item in directory list {path:?}"))?;
 
     Ok(names.into_iter().map(|dir| dir.path()).collect_vec())
 }
 
 /// Reads JSON from a path, trying to return the parsed value;
-pub fn read_json(path: &PathBuf) -> Result<Value> {
+pub fn read_json(path: &Path) -> Result<Value> {
     let string = read_to_string(path)
         .with_context(|| format!("while trying to parse path {path:?}"))?;
 
@@ -34,19 +192,20 @@ pub fn filter_recipe(json: &Value) -> bool {
     category == "minecraft:crafting_shaped" || category == "minecraft:crafting_shapeless"
 }
 
-/// Tries to parse a recipe from the provided JSON.
-pub fn parse_recipe(json: Value) -> Result<(String, Recipe)> {
+/// Tries to parse a recipe from the provided JSON, resolving tags with the
+/// provided registry and keeping only the materials in the provided palette.
+pub fn parse_recipe(json: Value, tags: &mut Tags, palette: &Palette) -> Result<(String, Recipe)> {
     let Some(Value::String(category)) = json.get("type") else {
         return Err(anyhow!("expected string category at path type'"));
     };
 
-    fn parse_shaped(json: &Value) -> Result<Recipe> {
+    fn parse_shaped(json: &Value, tags: &mut Tags, palette: &Palette) -> Result<Recipe> {
         let Some(Value::Object(object)) = json.get("key") else {
             return Err(anyhow!("expected object at path 'key'"));
         };
 
         let key = object.iter()
-            .map(|(k, v)| parse_ingredient(v).map(|v| (k, v)))
+            .map(|(k, v)| parse_ingredient(v, tags, palette).map(|v| (k, v)))
             .collect::<Result<HashMap<_, _>, _>>()
             .with_context(|| "while parsing ingredient line")?;
 
@@ -61,21 +220,21 @@ pub fn parse_recipe(json: Value) -> Result<(String, Recipe)> {
             .map(Recipe::Shaped)
     }
 
-    fn parse_shapeless(json: &Value) -> Result<Recipe> {
+    fn parse_shapeless(json: &Value, tags: &mut Tags, palette: &Palette) -> Result<Recipe> {
         let Some(Value::Array(array)) = json.get("ingredients") else {
             return Err(anyhow!("expected array at path 'ingredients'"));
         };
 
         array.iter()
-            .map(parse_ingredient)
+            .map(|v| parse_ingredient(v, tags, palette))
             .collect::<Result<Vec<Ingredient>>>()
             .with_context(|| "while parsing shapeless recipe")
             .map(Recipe::Shapeless)
     }
 
     let recipe = match category.as_ref() {
-        "minecraft:crafting_shaped" => parse_shaped(&json).with_context(|| "while parsing shaped recipe"),
-        "minecraft:crafting_shapeless" => parse_shapeless(&json).with_context(|| "while parsing shapeless recipe"),
+        "minecraft:crafting_shaped" => parse_shaped(&json, tags, palette).with_context(|| "while parsing shaped recipe"),
+        "minecraft:crafting_shapeless" => parse_shapeless(&json, tags, palette).with_context(|| "while parsing shapeless recipe"),
         _ => Err(anyhow!("invalid category {}", category)),
     }?;
 
@@ -104,7 +263,7 @@ fn parse_line(line: &Value, key: &HashMap<&String, Ingredient>) -> Result<Vec<In
 
 /// Parses an ingredient from JSON.
 /// This will fully expand all tags.
-fn parse_ingredient(value: &Value) -> Result<Ingredient> {
+fn parse_ingredient(value: &Value, tags: &mut Tags, palette: &Palette) -> Result<Ingredient> {
     if let Value::Object(object) = value {
         if object.len() != 1 {
             return Err(anyhow!("invalid input length: {:?}", object));
@@ -116,13 +275,13 @@ fn parse_ingredient(value: &Value) -> Result<Ingredient> {
 
         match key.as_ref() {
             "item" => Ok(
-                if let Some(material) = material_from_str(str) {
+                if let Some(material) = palette.material(str) {
                     vec![material]
                 } else {
                     vec![]
                 }
             ),
-            "tag" => parse_tag(str)
+            "tag" => parse_tag(str, tags, palette)
                 .with_context(|| format!("while parsing ingredient {:?}", value)),
             t => Err(anyhow!("invalid ingredient type {}", t)),
         }
@@ -130,7 +289,7 @@ fn parse_ingredient(value: &Value) -> Result<Ingredient> {
         let mut results = Vec::new();
 
         for elem in array {
-            let mut appended = parse_ingredient(elem)
+            let mut appended = parse_ingredient(elem, tags, palette)
                 .with_context(|| "while parsing list of ingredients")?;
 
             results.append(&mut appended);
@@ -142,68 +301,36 @@ fn parse_ingredient(value: &Value) -> Result<Ingredient> {
     }
 }
 
-/// Expands a tag into a list of ingredients.
-/// This will fully read any relevant tag files each time.
-fn parse_tag(name: &str) -> Result<Ingredient> {
-    if !name.starts_with("minecraft:") {
-        return Err(anyhow!("invalid name: {}", name));
-    }
+/// Expands a tag into a list of ingredients, keeping only the materials in
+/// the provided palette.
+fn parse_tag(name: &str, tags: &mut Tags, palette: &Palette) -> Result<Ingredient> {
+    let ids = tags.resolve(name)?;
 
-    let name = &name["minecraft:".len()..];
+    Ok(ids.iter().filter_map(|id| palette.material(id)).collect_vec())
+}
 
-    let mut path = PathBuf::from_str(TAG_DIRECTORY).unwrap();
-    path.push(format!("{name}.json"));
+#[cfg(test)]
+mod tests {
+    use std::fs;
 
-    let json = read_json(&path)
-        .with_context(|| format!("while parsing tag '{name}'"))?;
+    use super::*;
 
-    let Some(inputs) = json.get("values").and_then(Value::as_array) else {
-        return Err(anyhow!("could not find JSON array at path 'values'"));
-    };
+    #[test]
+    fn reads_tags_from_namespaces_next_to_the_data_root() {
+        let data = std::env::temp_dir().join(format!("solving-minecraftle-namespaces-{}", std::process::id()));
+        fs::create_dir_all(data.join("minecraft").join(TAG_DIRECTORY)).unwrap();
+        fs::create_dir_all(data.join("c").join(TAG_DIRECTORY)).unwrap();
+        fs::write(data.join("c").join(TAG_DIRECTORY).join("ingots.json"), r#"{"values":["minecraft:iron_ingot"]}"#).unwrap();
 
-    let Some(inputs) = inputs.into_iter().map(Value::as_str).collect::<Option<Vec<_>>>() else {
-        return Err(anyhow!("non-string value in JSON array 'values'"));
-    };
+        // The data root is resolved even when it's written indirectly
+        let config = DataConfig::from_root(&data.join("c").join("..").join("minecraft"));
 
-    let mut result = Vec::new();
+        let tag = config.tag("c:ingots").unwrap();
+        let missing = config.tag("other:ingots").unwrap();
 
-    for string in inputs {
-        if string.starts_with("#") {
-            let mut parsed = parse_tag(&string["#".len()..])
-                .with_context(|| format!("while parsing tag '{name}'"))?;
-            result.append(&mut parsed);
-        } else {
-            if let Some(material) = material_from_str(string) {
-                result.push(material);
-            }
-        }
-    }
+        fs::remove_dir_all(&data).unwrap();
 
-    Ok(result)
-}
-
-/// Converts a string to a material.
-pub fn material_from_str(str: &str) -> Option<Material> {
-    Some(match str {
-        "minecraft:air" => Material::Air,
-        "minecraft:oak_planks" => Material::Planks,
-        "minecraft:cobblestone" => Material::Cobblestone,
-        "minecraft:stone" => Material::Stone,
-        "minecraft:glass" => Material::Glass,
-        "minecraft:white_wool" => Material::Wool,
-        "minecraft:stick" => Material::Stick,
-        "minecraft:coal" => Material::Coal,
-        "minecraft:diamond" => Material::Diamond,
-        "minecraft:gold_ingot" => Material::GoldIngot,
-        "minecraft:iron_ingot" => Material::IronIngot,
-        "minecraft:redstone" => Material::Redstone,
-        "minecraft:quartz" => Material::Quartz,
-        "minecraft:oak_slab" => Material::Slab,
-        "minecraft:oak_log" => Material::Log,
-        "minecraft:iron_nugget" => Material::IronNugget,
-        "minecraft:redstone_torch" => Material::RedstoneTorch,
-        "minecraft:string" => Material::String,
-        "minecraft:leather" => Material::Leather,
-        _ => return None,
-    })
-}
\ No newline at end of file
+        assert_eq!(tag.unwrap()["values"][0], "minecraft:iron_ingot");
+        assert!(missing.is_none());
+    }
+}
@@ -1,7 +1,7 @@
 use itertools::Itertools;
 use std::iter::zip;
 
-use crate::{Craft, Ingredient, Material, Recipe};
+use crate::{labels::Origin, Answer, Craft, Ingredient, Material, Placement, Recipe};
 
 pub fn permutations_guess<'a>(recipe: &'a Recipe) -> Vec<Craft<'a>> {
     match recipe {
@@ -10,14 +10,38 @@ pub fn permutations_guess<'a>(recipe: &'a Recipe) -> Vec<Craft<'a>> {
     }
 } 
 
-pub fn permutations_answer<'a, F: Fn((usize, usize)) -> (usize, usize)>(recipe: &'a Recipe, location: F) -> Vec<Craft<'a>> {
+/// Generates every answer for a recipe. If `mirrored` is true, shaped recipes
+/// also have their mirrored pattern as answers, placed at the same location.
+/// Answers are numbered by their variant, but the index and recipe are left
+/// for the caller to fill in.
+pub fn permutations_answer<F, G>(recipe: &Recipe, location: F, slots: G, mirrored: bool) -> Vec<Answer>
+where
+    F: Fn((usize, usize)) -> (usize, usize),
+    G: Fn(usize) -> Vec<usize>,
+{
     match recipe {
-        Recipe::Shaped(grid) => permutations_shaped_for(grid, location(grid_size(grid))),
-        Recipe::Shapeless(_) => Vec::new(),
+        Recipe::Shaped(grid) => {
+            let offset = location(grid_size(grid));
+
+            let mut crafts = permutations_shaped_for(grid, offset);
+            if mirrored {
+                crafts.extend(permutations_shaped_for(&mirror_grid(grid), offset));
+            }
+
+            crafts.into_iter()
+                .unique()
+                .enumerate()
+                .map(|(variant, craft)| Answer { index: 0, origin: Origin { recipe: 0, variant }, craft, placement: Placement::Shaped })
+                .collect_vec()
+        },
+        Recipe::Shapeless(ingredients) => permutations_shapeless_for(ingredients, &slots(ingredients.len())).into_iter()
+            .enumerate()
+            .map(|(variant, craft)| Answer { index: 0, origin: Origin { recipe: 0, variant }, craft, placement: Placement::Shapeless })
+            .collect_vec(),
     }
 }
 
-fn permutations_shapeless<'a>(ingredients: &'a Vec<Ingredient>) -> Vec<Craft<'a>> {
+fn permutations_shapeless<'a>(ingredients: &'a [Ingredient]) -> Vec<Craft<'a>> {
 
     // A list of every possible slot combination for the ingredients
     let slots = (0..9usize).permutations(ingredients.len());
@@ -50,16 +74,40 @@ fn permutations_shapeless<'a>(ingredients: &'a Vec<Ingredient>) -> Vec<Craft<'a>
     }).unique().collect()
 }
 
-fn permutations_shaped<'a>(grid: &'a Vec<Vec<Ingredient>>) -> Vec<Craft<'a>> {
-    let width = grid.iter().map(Vec::len).min().unwrap_or(0);
-    let height = grid.len();
+/// Places every unique combination of the ingredients into the provided slots,
+/// in sorted order so that each combination only appears once.
+fn permutations_shapeless_for(ingredients: &[Ingredient], slots: &[usize]) -> Vec<Craft<'static>> {
+    ingredients.iter().multi_cartesian_product()
+        .map(|mut i| {
+            i.sort();
+            i
+        })
+        .unique()
+        .map(|materials| {
+            let mut craft = [Material::default(); 9];
+            for (material, slot) in zip(materials, slots) {
+                craft[*slot] = *material;
+            }
+            craft
+        })
+        .collect_vec()
+}
+
+/// Generates every placement of a shaped recipe, both as written and mirrored
+/// left-to-right. Symmetric patterns only appear once.
+fn permutations_shaped<'a>(grid: &'a [Vec<Ingredient>]) -> Vec<Craft<'a>> {
+    let (width, height) = grid_size(grid);
+
+    let mirrored = mirror_grid(grid);
 
     Itertools::cartesian_product(0..=3-width, 0..=3-height)
-        .map(|offset| permutations_shaped_for(grid, offset))
-        .flatten().collect_vec()
+        .flat_map(|offset| [permutations_shaped_for(grid, offset), permutations_shaped_for(&mirrored, offset)])
+        .flatten()
+        .unique()
+        .collect_vec()
 }
 
-fn permutations_shaped_for<'a>(grid: &'a Vec<Vec<Ingredient>>, (ox, oy): (usize, usize)) -> Vec<Craft<'a>> {
+fn permutations_shaped_for<'a>(grid: &'a [Vec<Ingredient>], (ox, oy): (usize, usize)) -> Vec<Craft<'a>> {
     let mut crafts = Vec::new();
 
     let (width, height) = grid_size(grid);
@@ -77,6 +125,117 @@ fn permutations_shaped_for<'a>(grid: &'a Vec<Vec<Ingredient>>, (ox, oy): (usize,
     crafts.into_iter().unique().collect_vec()
 }
 
-fn grid_size(grid: &Vec<Vec<Ingredient>>) -> (usize, usize) {
+/// The width and height of the pattern of a shaped recipe.
+pub fn grid_size(grid: &[Vec<Ingredient>]) -> (usize, usize) {
     (grid.iter().map(Vec::len).min().unwrap_or(0), grid.len())
-}
\ No newline at end of file
+}
+
+/// Mirrors the pattern of a shaped recipe left-to-right.
+fn mirror_grid(grid: &[Vec<Ingredient>]) -> Vec<Vec<Ingredient>> {
+    let (width, _) = grid_size(grid);
+
+    grid.iter()
+        .map(|row| row[..width].iter().rev().cloned().collect_vec())
+        .collect_vec()
+}
+
+/// Moves a craft as far up and left as possible.
+pub fn normalize(craft: &Craft) -> Craft<'static> {
+    let (left, top, _, _) = bounds(craft);
+
+    let mut normalized = [Material::default(); 9];
+
+    for (x, y) in Itertools::cartesian_product(left..3, top..3) {
+        normalized[x - left + (y - top) * 3] = craft[x + y * 3];
+    }
+
+    normalized
+}
+
+/// Mirrors a craft left-to-right.
+pub fn mirror(craft: &Craft) -> Craft<'static> {
+    let mut mirrored = *craft;
+
+    for row in mirrored.chunks_mut(3) {
+        row.reverse();
+    }
+
+    mirrored
+}
+
+/// Returns every unique way a craft can be placed: moved to any offset within
+/// the grid, and mirrored left-to-right.
+pub fn placements(craft: &Craft) -> Vec<Craft<'static>> {
+    let (left, top, right, bottom) = bounds(craft);
+
+    let (width, height) = (right + 1 - left, bottom + 1 - top);
+
+    [normalize(craft), normalize(&mirror(craft))].into_iter()
+        .flat_map(|craft| Itertools::cartesian_product(0..=3-width, 0..=3-height)
+            .map(move |(ox, oy)| {
+                let mut placed = [Material::default(); 9];
+                for (x, y) in Itertools::cartesian_product(0..width, 0..height) {
+                    placed[x + ox + (y + oy) * 3] = craft[x + y * 3];
+                }
+                placed
+            }))
+        .unique()
+        .collect_vec()
+}
+
+/// Returns the column and row of the top left corner of a craft's materials.
+pub fn offset(craft: &Craft) -> (usize, usize) {
+    let (left, top, _, _) = bounds(craft);
+
+    (left, top)
+}
+
+/// Returns the bounding box of the non-air materials of a craft, as the
+/// inclusive (left, top, right, bottom) coordinates.
+/// An empty craft is treated as filling the entire grid.
+fn bounds(craft: &Craft) -> (usize, usize, usize, usize) {
+    let filled = (0..9).filter(|i| craft[*i] != Material::default()).collect_vec();
+
+    if filled.is_empty() {
+        return (0, 0, 2, 2);
+    }
+
+    let xs = filled.iter().map(|i| i % 3);
+    let ys = filled.iter().map(|i| i / 3);
+
+    (xs.clone().min().unwrap(), ys.clone().min().unwrap(), xs.max().unwrap(), ys.max().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{get_shaped_offset, get_shapeless_slots, tests::{AIR, PLANKS, STICK}};
+
+    use super::*;
+
+    #[test]
+    fn places_crafts_at_every_offset_and_mirrored() {
+        let stick = [AIR, AIR, AIR, AIR, PLANKS, AIR, AIR, PLANKS, AIR];
+        let hoe = [PLANKS, PLANKS, AIR, AIR, STICK, AIR, AIR, STICK, AIR];
+
+        assert_eq!(mirror(&hoe), [AIR, PLANKS, PLANKS, AIR, STICK, AIR, AIR, STICK, AIR]);
+        assert_eq!(normalize(&stick), [PLANKS, AIR, AIR, PLANKS, AIR, AIR, AIR, AIR, AIR]);
+
+        assert_eq!(placements(&stick).len(), 6);
+        assert_eq!(placements(&hoe).len(), 4);
+        assert!(placements(&hoe).contains(&[AIR, PLANKS, PLANKS, AIR, AIR, STICK, AIR, AIR, STICK]));
+    }
+
+    #[test]
+    fn generates_mirrored_patterns_once() {
+        let symmetric = Recipe::Shaped(vec![vec![vec![PLANKS], vec![PLANKS]]]);
+        let asymmetric = Recipe::Shaped(vec![vec![vec![PLANKS], vec![STICK]]]);
+
+        assert_eq!(permutations_guess(&symmetric).len(), 6);
+        assert_eq!(permutations_guess(&asymmetric).len(), 12);
+
+        let answers = |recipe, mirrored| permutations_answer(recipe, get_shaped_offset, get_shapeless_slots, mirrored).len();
+
+        assert_eq!((answers(&symmetric, false), answers(&symmetric, true)), (1, 1));
+        assert_eq!((answers(&asymmetric, false), answers(&asymmetric, true)), (1, 2));
+    }
+}
@@ -0,0 +1,199 @@
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use itertools::Itertools;
+use serde_json::{json, Value};
+
+use crate::{book::Book, calculate_hint, greedy_algorithm_against_answer, modified_greedy, optimal, Answer, Craft, SolverContext};
+
+/// The number of worst-case answers listed in a report.
+const WORST_CASES: usize = 10;
+
+/// A strategy that can be run against every answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Strategy {
+    /// The greedy algorithm.
+    Greedy,
+    /// The greedy algorithm, starting with the opening book.
+    ModifiedGreedy,
+    /// The strategy that provably minimizes the average number of guesses.
+    Optimal,
+}
+
+/// How a report is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// A human-readable summary.
+    Table,
+    /// Every statistic, as a JSON object.
+    Json,
+    /// The number of guesses for every answer, as CSV rows.
+    Csv,
+}
+
+/// The results of running a strategy against every answer.
+pub struct Report<'a> {
+    strategy: Strategy,
+    answers: &'a [Answer],
+    /// The number of guesses for every answer, by index.
+    guesses: Vec<usize>,
+    limit: usize,
+    runtime: Duration,
+}
+
+impl<'a> Report<'a> {
+    /// Runs the strategy against every answer. `book` is only used by
+    /// [Strategy::ModifiedGreedy], and `limit` is the number of guesses that
+    /// an answer must be solved within.
+    pub fn run(strategy: Strategy, answers: &'a [Answer], guesses: &[Craft], ctx: SolverContext, book: Option<&Book>, limit: usize) -> Self {
+        let start = Instant::now();
+
+        let counts = match strategy {
+            Strategy::Greedy => answers.iter()
+                .map(|answer| greedy_algorithm_against_answer(answers, guesses, ctx, answer))
+                .collect_vec(),
+            Strategy::ModifiedGreedy => answers.iter()
+                .map(|answer| modified_greedy(answers, guesses, ctx, book, |guess| calculate_hint(answer, guess)))
+                .collect_vec(),
+            Strategy::Optimal => {
+                let mut search = optimal::Search::new(answers, guesses, ctx, optimal::Objective::Average, None, limit);
+                search.solve();
+
+                let tree = search.tree();
+                answers.iter().map(|answer| tree.play(answer)).collect_vec()
+            },
+        };
+
+        Self { strategy, answers, guesses: counts, limit, runtime: start.elapsed() }
+    }
+
+    fn average(&self) -> f64 {
+        self.guesses.iter().sum::<usize>() as f64 / self.guesses.len().max(1) as f64
+    }
+
+    fn max(&self) -> usize {
+        self.guesses.iter().copied().max().unwrap_or(0)
+    }
+
+    fn failures(&self) -> usize {
+        self.guesses.iter().filter(|count| **count > self.limit).count()
+    }
+
+    /// The number of answers solved in each number of guesses.
+    fn histogram(&self) -> Vec<(usize, usize)> {
+        self.guesses.iter().copied().counts().into_iter().sorted().collect_vec()
+    }
+
+    /// The number of answers, average guesses, and maximum guesses of every
+    /// recipe, in the order they were loaded.
+    fn recipes(&self) -> Vec<(usize, usize, f64, usize)> {
+        self.answers.iter()
+            .zip(&self.guesses)
+            .into_group_map_by(|(answer, _)| answer.origin.recipe)
+            .into_iter()
+            .sorted_by_key(|(recipe, _)| *recipe)
+            .map(|(recipe, results)| {
+                let total = results.iter().map(|(_, count)| **count).sum::<usize>();
+                let max = results.iter().map(|(_, count)| **count).max().unwrap_or(0);
+
+                (recipe, results.len(), total as f64 / results.len() as f64, max)
+            })
+            .collect_vec()
+    }
+
+    /// The answers that took the most guesses, worst first.
+    fn worst_cases(&self) -> Vec<(&Answer, usize)> {
+        self.answers.iter()
+            .zip(self.guesses.iter().copied())
+            .sorted_by_key(|(answer, count)| (std::cmp::Reverse(*count), answer.index))
+            .take(WORST_CASES)
+            .collect_vec()
+    }
+
+    /// Writes the report in the provided format.
+    pub fn render(&self, format: Format, ctx: SolverContext) -> String {
+        match format {
+            Format::Table => self.to_table(ctx),
+            Format::Json => serde_json::to_string_pretty(&self.to_json(ctx)).expect("reports are valid JSON"),
+            Format::Csv => self.to_csv(ctx),
+        }
+    }
+
+    fn strategy_name(&self) -> String {
+        self.strategy.to_possible_value().expect("strategies aren't skipped").get_name().to_owned()
+    }
+
+    fn to_table(&self, ctx: SolverContext) -> String {
+        let mut lines = vec![
+            format!("Strategy: {}", self.strategy_name()),
+            format!("Answers: {}, average: {:.4}, max: {}, failures within {} guesses: {}, runtime: {:.3}s",
+                self.guesses.len(), self.average(), self.max(), self.limit, self.failures(), self.runtime.as_secs_f64()),
+            String::new(),
+            format!("{:>7}  {:>7}", "Guesses", "Answers"),
+        ];
+
+        for (count, answers) in self.histogram() {
+            lines.push(format!("{count:>7}  {answers:>7}  {}", "#".repeat(answers)));
+        }
+
+        let recipes = self.recipes();
+        let width = recipes.iter().map(|(recipe, ..)| ctx.labels.name(*recipe).len()).max().unwrap_or(0).max("Recipe".len());
+
+        lines.push(String::new());
+        lines.push(format!("{:<width$}  {:>7}  {:>7}  {:>3}", "Recipe", "Answers", "Average", "Max"));
+
+        for (recipe, answers, average, max) in recipes {
+            lines.push(format!("{:<width$}  {answers:>7}  {average:>7.3}  {max:>3}", ctx.labels.name(recipe)));
+        }
+
+        lines.push(String::new());
+        lines.push("Worst cases:".to_owned());
+
+        for (answer, count) in self.worst_cases() {
+            lines.push(format!("{count:>3}  {}", ctx.labels.answer(answer)));
+        }
+
+        lines.join("\n")
+    }
+
+    fn to_json(&self, ctx: SolverContext) -> Value {
+        json!({
+            "strategy": self.strategy_name(),
+            "answers": self.guesses.len(),
+            "average": self.average(),
+            "max": self.max(),
+            "guess_limit": self.limit,
+            "failures": self.failures(),
+            "runtime_seconds": self.runtime.as_secs_f64(),
+            "histogram": self.histogram().into_iter()
+                .map(|(guesses, answers)| json!({ "guesses": guesses, "answers": answers }))
+                .collect_vec(),
+            "recipes": self.recipes().into_iter()
+                .map(|(recipe, answers, average, max)| json!({
+                    "recipe": ctx.labels.name(recipe),
+                    "answers": answers,
+                    "average": average,
+                    "max": max,
+                }))
+                .collect_vec(),
+            "worst_cases": self.worst_cases().into_iter()
+                .map(|(answer, guesses)| json!({ "answer": ctx.labels.answer(answer), "index": answer.index, "guesses": guesses }))
+                .collect_vec(),
+            "results": self.answers.iter().zip(&self.guesses)
+                .map(|(answer, guesses)| json!({
+                    "index": answer.index,
+                    "recipe": ctx.labels.name(answer.origin.recipe),
+                    "variant": answer.origin.variant,
+                    "guesses": guesses,
+                }))
+                .collect_vec(),
+        })
+    }
+
+    fn to_csv(&self, ctx: SolverContext) -> String {
+        let rows = self.answers.iter().zip(&self.guesses)
+            .map(|(answer, guesses)| format!("{},{},{},{}", answer.index, ctx.labels.name(answer.origin.recipe), answer.origin.variant, guesses));
+
+        ["index,recipe,variant,guesses".to_owned()].into_iter().chain(rows).join("\n")
+    }
+}
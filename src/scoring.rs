@@ -0,0 +1,102 @@
+use std::cmp::Ordering;
+
+use clap::ValueEnum;
+use itertools::Itertools;
+
+/// The score of a guess. Lower scores are better, and scores are compared
+/// lexicographically so that later values break ties.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score(pub Vec<f64>);
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for (l, r) in self.0.iter().zip(&other.0) {
+            match l.total_cmp(r) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            }
+        }
+
+        self.0.len().cmp(&other.0.len())
+    }
+}
+
+/// Decides how good a guess is from the pools it splits the answers into.
+pub trait Scorer {
+    /// Scores a guess by the sizes of its pools, sorted from largest to
+    /// smallest. Lower scores are better.
+    fn score(&self, pools: &[usize]) -> Score;
+}
+
+/// Minimizes the size of the largest pool, then the second largest, and so on.
+pub struct Minimax;
+
+impl Scorer for Minimax {
+    fn score(&self, pools: &[usize]) -> Score {
+        Score(pools.iter().map(|size| *size as f64).collect_vec())
+    }
+}
+
+/// Maximizes the Shannon entropy of the hint, in bits.
+pub struct Entropy;
+
+impl Scorer for Entropy {
+    fn score(&self, pools: &[usize]) -> Score {
+        let total = pools.iter().sum::<usize>() as f64;
+
+        let entropy = pools.iter()
+            .map(|size| *size as f64 / total)
+            .map(|p| -p * p.log2())
+            .sum::<f64>();
+
+        Score(vec![-entropy])
+    }
+}
+
+/// Minimizes the expected size of the pool that's left after the guess.
+pub struct ExpectedSize;
+
+impl Scorer for ExpectedSize {
+    fn score(&self, pools: &[usize]) -> Score {
+        let total = pools.iter().sum::<usize>() as f64;
+
+        Score(vec![pools.iter().map(|size| (size * size) as f64).sum::<f64>() / total])
+    }
+}
+
+/// Maximizes the number of pools.
+pub struct MostParts;
+
+impl Scorer for MostParts {
+    fn score(&self, pools: &[usize]) -> Score {
+        Score(vec![-(pools.len() as f64)])
+    }
+}
+
+/// The scorers that can be picked from the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ScorerKind {
+    Minimax,
+    Entropy,
+    ExpectedSize,
+    MostParts,
+}
+
+impl ScorerKind {
+    pub fn scorer(self) -> &'static dyn Scorer {
+        match self {
+            ScorerKind::Minimax => &Minimax,
+            ScorerKind::Entropy => &Entropy,
+            ScorerKind::ExpectedSize => &ExpectedSize,
+            ScorerKind::MostParts => &MostParts,
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use itertools::Itertools;
+use serde_json::{json, Value};
+
+use crate::{labels::Labels, palette::Palette, permutations::grid_size, report::Format, Answer, Craft, Material, Recipe};
+
+/// The number of most common materials listed for each slot in a table.
+const SLOT_MATERIALS: usize = 5;
+
+/// Statistics about the loaded recipes and the guesses and answers generated
+/// from them.
+pub struct Stats {
+    /// The number of recipes, answers, and guesses that use each material, in
+    /// palette order.
+    materials: Vec<(Material, usize, usize, usize)>,
+    /// How many answers have each material in each slot, most common first.
+    slots: Vec<Vec<(Material, usize)>>,
+    /// The number of shaped recipes with each pattern width and height.
+    shaped_sizes: Vec<((usize, usize), usize)>,
+    /// The number of shapeless recipes with each number of ingredients.
+    shapeless_sizes: Vec<(usize, usize)>,
+    /// The number of ingredients that allow each number of materials.
+    alternatives: Vec<(usize, usize)>,
+    /// The recipes with an ingredient that has no materials in the palette,
+    /// which can't be guessed or be the answer.
+    dropped: Vec<usize>,
+    recipes: usize,
+    answers: usize,
+    guesses: usize,
+}
+
+impl Stats {
+    pub fn new(recipes: &[(String, Recipe)], answers: &[Answer], guesses: &[Craft], palette: &Palette) -> Self {
+        // Each ingredient only counts once per recipe, however often it's used
+        let ingredients = recipes.iter()
+            .map(|(_, recipe)| recipe.ingredients().into_iter().unique().collect_vec())
+            .collect_vec();
+
+        let materials = palette.materials()
+            .map(|material| (
+                material,
+                ingredients.iter().filter(|ingredients| ingredients.iter().any(|ingredient| ingredient.contains(&material))).count(),
+                answers.iter().filter(|answer| answer.craft.contains(&material)).count(),
+                guesses.iter().filter(|guess| guess.contains(&material)).count(),
+            ))
+            .collect_vec();
+
+        let slots = (0..9)
+            .map(|slot| answers.iter()
+                .map(|answer| answer.craft[slot])
+                .counts()
+                .into_iter()
+                .sorted_by_key(|(material, count)| (std::cmp::Reverse(*count), *material))
+                .collect_vec())
+            .collect_vec();
+
+        let shaped_sizes = recipes.iter()
+            .filter_map(|(_, recipe)| match recipe {
+                Recipe::Shaped(grid) => Some(grid_size(grid)),
+                Recipe::Shapeless(_) => None,
+            })
+            .counts()
+            .into_iter()
+            .sorted()
+            .collect_vec();
+
+        let shapeless_sizes = recipes.iter()
+            .filter_map(|(_, recipe)| match recipe {
+                Recipe::Shaped(_) => None,
+                Recipe::Shapeless(ingredients) => Some(ingredients.len()),
+            })
+            .counts()
+            .into_iter()
+            .sorted()
+            .collect_vec();
+
+        let alternatives = ingredients.iter()
+            .flatten()
+            .map(|ingredient| ingredient.iter().unique().count())
+            .counts()
+            .into_iter()
+            .sorted()
+            .collect_vec();
+
+        let dropped = ingredients.iter()
+            .positions(|ingredients| ingredients.iter().any(|ingredient| ingredient.is_empty()))
+            .collect_vec();
+
+        Self {
+            materials,
+            slots,
+            shaped_sizes,
+            shapeless_sizes,
+            alternatives,
+            dropped,
+            recipes: recipes.len(),
+            answers: answers.len(),
+            guesses: guesses.len(),
+        }
+    }
+
+    /// Writes the statistics in the provided format. As CSV, only the
+    /// frequency of each material is written.
+    pub fn render(&self, format: Format, palette: &Palette, labels: &Labels) -> String {
+        match format {
+            Format::Table => self.to_table(palette, labels),
+            Format::Json => serde_json::to_string_pretty(&self.to_json(palette, labels)).expect("statistics are valid JSON"),
+            Format::Csv => self.to_csv(palette),
+        }
+    }
+
+    fn to_table(&self, palette: &Palette, labels: &Labels) -> String {
+        let width = self.materials.iter().map(|(material, ..)| palette.name(*material).len()).max().unwrap_or(0).max("Material".len());
+
+        let mut lines = vec![
+            format!("Recipes: {}, answers: {}, guesses: {}", self.recipes, self.answers, self.guesses),
+            String::new(),
+            format!("{:<width$}  {:>7}  {:>7}  {:>7}", "Material", "Recipes", "Answers", "Guesses"),
+        ];
+
+        for (material, recipes, answers, guesses) in &self.materials {
+            lines.push(format!("{:<width$}  {recipes:>7}  {answers:>7}  {guesses:>7}", palette.name(*material)));
+        }
+
+        lines.push(String::new());
+        lines.push("Answer materials by slot:".to_owned());
+
+        for (slot, counts) in self.slots.iter().enumerate() {
+            let listed = counts.iter().take(SLOT_MATERIALS)
+                .map(|(material, count)| format!("{} {count}", palette.name(*material)))
+                .join(", ");
+
+            match counts.len().saturating_sub(SLOT_MATERIALS) {
+                0 => lines.push(format!("    {},{}: {listed}", slot % 3, slot / 3)),
+                others => lines.push(format!("    {},{}: {listed}, {others} more", slot % 3, slot / 3)),
+            }
+        }
+
+        lines.push(String::new());
+        lines.push(format!("Shaped pattern sizes: {}", self.shaped_sizes.iter().map(|((w, h), count)| format!("{w}x{h} {count}")).join(", ")));
+        lines.push(format!("Shapeless ingredient counts: {}", self.shapeless_sizes.iter().map(|(size, count)| format!("{size} {count}")).join(", ")));
+        lines.push(format!("Ingredients by number of materials: {}", self.alternatives.iter().map(|(size, count)| format!("{size} {count}")).join(", ")));
+
+        lines.push(String::new());
+        lines.push(format!("Dropped recipes (an ingredient has no materials in the palette): {}", self.dropped.len()));
+
+        for recipe in &self.dropped {
+            lines.push(format!("    {}", labels.name(*recipe)));
+        }
+
+        lines.join("\n")
+    }
+
+    fn to_json(&self, palette: &Palette, labels: &Labels) -> Value {
+        json!({
+            "recipes": self.recipes,
+            "answers": self.answers,
+            "guesses": self.guesses,
+            "materials": self.materials.iter()
+                .map(|(material, recipes, answers, guesses)| json!({
+                    "id": palette.info(*material).id,
+                    "recipes": recipes,
+                    "answers": answers,
+                    "guesses": guesses,
+                }))
+                .collect_vec(),
+            "slots": self.slots.iter()
+                .map(|counts| counts.iter()
+                    .map(|(material, count)| json!({ "id": palette.info(*material).id, "answers": count }))
+                    .collect_vec())
+                .collect_vec(),
+            "shaped_sizes": self.shaped_sizes.iter()
+                .map(|((width, height), count)| json!({ "width": width, "height": height, "recipes": count }))
+                .collect_vec(),
+            "shapeless_sizes": self.shapeless_sizes.iter()
+                .map(|(size, count)| json!({ "ingredients": size, "recipes": count }))
+                .collect_vec(),
+            "alternatives": self.alternatives.iter()
+                .map(|(size, count)| json!({ "materials": size, "ingredients": count }))
+                .collect_vec(),
+            "dropped": self.dropped.iter().map(|recipe| labels.name(*recipe)).collect_vec(),
+        })
+    }
+
+    fn to_csv(&self, palette: &Palette) -> String {
+        let rows = self.materials.iter()
+            .map(|(material, recipes, answers, guesses)| format!("{},{recipes},{answers},{guesses}", palette.info(*material).id));
+
+        ["material,recipes,answers,guesses".to_owned()].into_iter().chain(rows).join("\n")
+    }
+}
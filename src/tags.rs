@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context, Result};
+use itertools::Itertools;
+use serde_json::Value;
+
+use crate::parse::DataSource;
+
+/// The namespace of IDs that don't specify one.
+pub const DEFAULT_NAMESPACE: &str = "minecraft";
+
+/// Resolves item tags into the item IDs they contain. Each tag file is only
+/// read once, and each tag is only resolved once.
+pub struct Tags<'a> {
+    source: &'a dyn DataSource,
+    resolved: HashMap<String, Vec<String>>,
+}
+
+impl<'a> Tags<'a> {
+    pub fn new(source: &'a dyn DataSource) -> Self {
+        Self { source, resolved: HashMap::new() }
+    }
+
+    /// Returns every item ID in the tag with the provided ID, including those
+    /// of any tags it references.
+    pub fn resolve(&mut self, id: &str) -> Result<&[String]> {
+        let id = namespaced(id);
+
+        self.resolve_in(&id, true, &mut Vec::new())?;
+
+        Ok(&self.resolved[&id])
+    }
+
+    /// Resolves a tag, where `stack` is the tags currently being resolved.
+    /// Tags that aren't required resolve to nothing if they don't exist.
+    fn resolve_in(&mut self, id: &str, required: bool, stack: &mut Vec<String>) -> Result<Vec<String>> {
+        if let Some(items) = self.resolved.get(id) {
+            return Ok(items.clone());
+        }
+
+        if let Some(start) = stack.iter().position(|tag| tag == id) {
+            return Err(anyhow!("tag '{id}' references itself: {}", stack[start..].iter().chain([&id.to_owned()]).join(" -> ")));
+        }
+
+        let json = match self.source.tag(id).with_context(|| format!("while reading tag '{id}'"))? {
+            Some(json) => json,
+            None if !required => return Ok(Vec::new()),
+            None => return Err(anyhow!("could not find tag '{id}'")),
+        };
+
+        stack.push(id.to_owned());
+        let items = self.entries(&json, stack);
+        stack.pop();
+
+        let items = items.with_context(|| format!("while resolving tag '{id}'"))?;
+
+        self.resolved.insert(id.to_owned(), items.clone());
+
+        Ok(items)
+    }
+
+    /// Resolves the `values` of a tag. Each entry is either a string, or an
+    /// object with an `id` and an optional `required` flag. IDs starting with
+    /// `#` are tags.
+    fn entries(&mut self, json: &Value, stack: &mut Vec<String>) -> Result<Vec<String>> {
+        let Some(values) = json.get("values").and_then(Value::as_array) else {
+            return Err(anyhow!("could not find JSON array at path 'values'"));
+        };
+
+        let mut items = Vec::new();
+
+        for value in values {
+            let (entry, required) = match value {
+                Value::String(entry) => (entry.as_str(), true),
+                Value::Object(object) => {
+                    let Some(entry) = object.get("id").and_then(Value::as_str) else {
+                        return Err(anyhow!("expected string at path 'id' in tag entry {value}"));
+                    };
+
+                    (entry, object.get("required").and_then(Value::as_bool).unwrap_or(true))
+                },
+                _ => return Err(anyhow!("expected string or object in JSON array 'values', found {value}")),
+            };
+
+            // Items can't be checked for existence, so optional ones are kept
+            match entry.strip_prefix('#') {
+                Some(tag) => items.append(&mut self.resolve_in(&namespaced(tag), required, stack)?),
+                None => items.push(namespaced(entry)),
+            }
+        }
+
+        Ok(items)
+    }
+}
+
+/// Adds the default namespace to an ID that doesn't have one.
+pub fn namespaced(id: &str) -> String {
+    if id.contains(':') {
+        id.to_owned()
+    } else {
+        format!("{DEFAULT_NAMESPACE}:{id}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap};
+
+    use anyhow::Result;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Tags kept in memory, which records every tag it's asked for.
+    struct Memory {
+        tags: HashMap<&'static str, Value>,
+        reads: RefCell<Vec<String>>,
+    }
+
+    impl Memory {
+        fn new(tags: impl IntoIterator<Item = (&'static str, Value)>) -> Self {
+            Self { tags: tags.into_iter().collect(), reads: RefCell::new(Vec::new()) }
+        }
+    }
+
+    impl DataSource for Memory {
+        fn recipes(&self) -> Result<Vec<(String, Value)>> {
+            Ok(Vec::new())
+        }
+
+        fn tag(&self, id: &str) -> Result<Option<Value>> {
+            self.reads.borrow_mut().push(id.to_owned());
+            Ok(self.tags.get(id).cloned())
+        }
+    }
+
+    #[test]
+    fn resolves_nested_tags_once() {
+        let source = Memory::new([
+            ("minecraft:planks", json!({ "values": ["minecraft:oak_planks", "#minecraft:stems"] })),
+            ("minecraft:stems", json!({ "values": ["crimson_planks"] })),
+            ("minecraft:wood", json!({ "values": ["#planks", "#minecraft:stems"] })),
+        ]);
+        let mut tags = Tags::new(&source);
+
+        assert_eq!(tags.resolve("minecraft:wood").unwrap(), ["minecraft:oak_planks", "minecraft:crimson_planks", "minecraft:crimson_planks"]);
+        assert_eq!(tags.resolve("planks").unwrap(), ["minecraft:oak_planks", "minecraft:crimson_planks"]);
+
+        assert_eq!(source.reads.borrow().iter().sorted().collect_vec(), ["minecraft:planks", "minecraft:stems", "minecraft:wood"]);
+    }
+
+    #[test]
+    fn reports_cycles_with_the_chain_of_tags() {
+        let source = Memory::new([
+            ("minecraft:a", json!({ "values": ["#minecraft:b"] })),
+            ("minecraft:b", json!({ "values": ["#minecraft:a"] })),
+        ]);
+
+        let err = Tags::new(&source).resolve("minecraft:a").unwrap_err();
+
+        assert_eq!(err.root_cause().to_string(), "tag 'minecraft:a' references itself: minecraft:a -> minecraft:b -> minecraft:a");
+        assert_eq!(err.chain().map(|err| err.to_string()).collect_vec()[..2], ["while resolving tag 'minecraft:a'", "while resolving tag 'minecraft:b'"]);
+    }
+
+    #[test]
+    fn skips_optional_tags_that_dont_exist() {
+        let source = Memory::new([
+            ("minecraft:ingots", json!({ "values": [
+                "minecraft:iron_ingot",
+                { "id": "#c:ingots", "required": false },
+                { "id": "minecraft:copper_ingot", "required": false },
+            ] })),
+            ("minecraft:required", json!({ "values": [{ "id": "#c:ingots" }] })),
+        ]);
+        let mut tags = Tags::new(&source);
+
+        assert_eq!(tags.resolve("minecraft:ingots").unwrap(), ["minecraft:iron_ingot", "minecraft:copper_ingot"]);
+        assert_eq!(tags.resolve("minecraft:required").unwrap_err().root_cause().to_string(), "could not find tag 'c:ingots'");
+    }
+
+    #[test]
+    fn rejects_malformed_optional_tags() {
+        let source = Memory::new([
+            ("minecraft:ingots", json!({ "values": [{ "id": "#c:ingots", "required": false }] })),
+            ("c:ingots", json!({ "entries": [] })),
+        ]);
+
+        let err = Tags::new(&source).resolve("minecraft:ingots").unwrap_err();
+
+        assert_eq!(err.root_cause().to_string(), "could not find JSON array at path 'values'");
+    }
+}
@@ -0,0 +1,143 @@
+use itertools::Itertools;
+use serde_json::{json, Value};
+
+use crate::{assemble_pools, book::Book, calculate_hint, follow_book, labels::Labels, matrix::PackedHint, notation::to_notation, most_information, next_guesses, palette::Palette, Answer, Color, SolverContext, Craft, Hint};
+
+/// A node of a strategy's decision tree: the guess to make once the game has
+/// reached this point.
+#[derive(Debug, Clone)]
+pub struct Node {
+    /// The guess to make.
+    pub guess: Craft<'static>,
+    /// The number of answers that are still possible.
+    pub answers: usize,
+    /// What to do for each hint the guess can result in.
+    pub branches: Vec<Branch>,
+}
+
+/// A hint that a guess can result in, and what to do after it.
+#[derive(Debug, Clone)]
+pub struct Branch {
+    pub hint: Hint,
+    /// The number of answers that result in this hint.
+    pub answers: usize,
+    /// The next node, or `None` if the hint means the game has been solved.
+    pub next: Option<Node>,
+}
+
+/// Builds the decision tree of the greedy algorithm, forcing the guesses in
+/// the opening book at the start like [crate::modified_greedy].
+pub fn build_tree(answers: &[Answer], guesses: &[Craft], ctx: SolverContext, book: Option<&Book>) -> Node {
+    let guess = match book {
+        Some(book) => book.guess,
+        None => *most_information(answers, guesses, ctx).first().unwrap().1,
+    };
+
+    build_node(answers, guesses, ctx, guess, book)
+}
+
+/// Builds the node for a guess. `book` is the opening book that forced the
+/// guess, if any.
+fn build_node(answers: &[Answer], guesses: &[Craft], ctx: SolverContext, guess: Craft<'static>, book: Option<&Book>) -> Node {
+    let pools = assemble_pools(&guess, answers, ctx.matrix);
+
+    let branches = pools.into_iter()
+        .sorted_by_key(|(hint, pool)| (std::cmp::Reverse(pool.len()), *hint))
+        .map(|(hint, pool)| {
+            let next = if hint == PackedHint::SOLVED {
+                None
+            } else if pool.len() == 1 || (book.is_none() && pool.len() == answers.len()) {
+                // Either there's only one answer left, or the greedy guess
+                // didn't split the answers at all, so guess the first answer
+                // directly. Guesses forced by the book are followed regardless.
+                Some(build_node(&pool, guesses, ctx, pool[0].craft, None))
+            } else {
+                let guesses = next_guesses(guesses, &pool, ctx, &guess, &hint.unpack());
+                let book = follow_book(book, &hint.unpack(), &guesses, ctx);
+
+                Some(build_tree(&pool, &guesses, ctx, book))
+            };
+
+            Branch { hint: hint.unpack(), answers: pool.len(), next }
+        })
+        .collect_vec();
+
+    Node { guess, answers: answers.len(), branches }
+}
+
+impl Node {
+    /// Returns the number of guesses needed for every answer under this node.
+    pub fn guess_counts(&self) -> Vec<usize> {
+        self.branches.iter()
+            .flat_map(|branch| match &branch.next {
+                Some(next) => next.guess_counts().into_iter().map(|count| count + 1).collect_vec(),
+                None => vec![1; branch.answers],
+            })
+            .collect_vec()
+    }
+
+    /// Plays the strategy against an answer, returning the number of guesses
+    /// it takes to solve it.
+    pub fn play(&self, answer: &Answer) -> usize {
+        let hint = calculate_hint(answer, &self.guess);
+
+        let branch = self.branches.iter()
+            .find(|branch| branch.hint == hint)
+            .expect("every answer under a node has a branch");
+
+        match &branch.next {
+            Some(next) => 1 + next.play(answer),
+            None => 1,
+        }
+    }
+
+    /// Serializes the tree to JSON, with crafts as lists of item IDs along
+    /// with their grid notation and the recipe they craft.
+    pub fn to_json(&self, palette: &Palette, labels: &Labels) -> Value {
+        json!({
+            "guess": self.guess.iter().map(|m| &palette.info(*m).id).collect_vec(),
+            "recipe": labels.guess(&self.guess),
+            "grid": to_notation(&self.guess, palette),
+            "answers": self.answers,
+            "branches": self.branches.iter().map(|branch| json!({
+                "hint": hint_to_string(&branch.hint),
+                "answers": branch.answers,
+                "next": branch.next.as_ref().map(|next| next.to_json(palette, labels)),
+            })).collect_vec(),
+        })
+    }
+}
+
+/// Converts a hint to a string, with `G` for green, `Y` for yellow, and `.`
+/// for gray.
+pub fn hint_to_string(hint: &Hint) -> String {
+    hint.iter().map(|color| match color {
+        Color::Green => 'G',
+        Color::Yellow => 'Y',
+        Color::Gray => '.',
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{modified_greedy, tests::Fixture, HintModel};
+
+    use super::*;
+
+    #[test]
+    fn follows_books_that_repeat_a_guess_like_the_simulation() {
+        let fixture = Fixture::recipes(HintModel::AnyPlacement);
+        let book = Book::linear(&[fixture.guesses[0], fixture.guesses[0], fixture.guesses[1]]).unwrap();
+
+        for hard_mode in [false, true] {
+            let ctx = fixture.ctx(hard_mode);
+            let tree = build_tree(&fixture.answers, &fixture.guesses, ctx, Some(&book));
+
+            for answer in &fixture.answers {
+                let simulated = modified_greedy(&fixture.answers, &fixture.guesses, ctx, Some(&book), |guess| calculate_hint(answer, guess));
+
+                assert_eq!(tree.play(answer), simulated, "{answer:?}");
+            }
+        }
+    }
+}